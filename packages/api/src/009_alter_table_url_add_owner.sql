@@ -0,0 +1,5 @@
+alter table url add column user_id blob references user(id) on delete set null on update cascade;
+alter table url add column organisation_id blob references organisation(id) on delete set null on update cascade;
+
+create index if not exists url_user_id on url (user_id);
+create index if not exists url_organisation_id on url (organisation_id);
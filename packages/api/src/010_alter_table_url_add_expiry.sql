@@ -0,0 +1,5 @@
+alter table url add column expires_at integer;
+alter table url add column max_clicks integer;
+alter table url add column fallback_url text;
+
+create index if not exists url_analytics_url_id on url_analytics (url_id);
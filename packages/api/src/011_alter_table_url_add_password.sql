@@ -0,0 +1 @@
+alter table url add column password text;
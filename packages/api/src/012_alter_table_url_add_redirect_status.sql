@@ -0,0 +1 @@
+alter table url add column redirect_status integer not null default 307 check (redirect_status in (301, 302, 307, 308));
@@ -0,0 +1,2 @@
+alter table url add column forward_query integer not null default false;
+alter table url add column forward_path integer not null default false;
@@ -0,0 +1,5 @@
+alter table organisation add column utm_source text;
+alter table organisation add column utm_medium text;
+alter table organisation add column utm_campaign text;
+alter table organisation add column utm_term text;
+alter table organisation add column utm_content text;
@@ -0,0 +1,3 @@
+alter table url add column ios_url text;
+alter table url add column android_url text;
+alter table url add column desktop_url text;
@@ -0,0 +1 @@
+alter table url_analytics add column req_country text;
@@ -0,0 +1,14 @@
+create table if not exists url_variant (
+    id blob not null,
+    url_id blob not null,
+
+    url text not null,
+    weight integer not null check (weight > 0),
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (id),
+    unique (url_id, url),
+    foreign key (url_id) references url(id) on delete cascade on update cascade
+) strict;
@@ -0,0 +1 @@
+alter table url_analytics add column url_variant_id blob references url_variant(id) on delete set null on update cascade;
@@ -0,0 +1,2 @@
+alter table url add column active_from integer;
+alter table url add column active_until integer;
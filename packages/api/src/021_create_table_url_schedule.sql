@@ -0,0 +1,14 @@
+create table if not exists url_schedule (
+    id blob not null,
+    url_id blob not null,
+
+    starts_at integer not null,
+    url text not null,
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (id),
+    unique (url_id, starts_at),
+    foreign key (url_id) references url(id) on delete cascade on update cascade
+) strict;
@@ -0,0 +1,3 @@
+alter table url add column og_title text;
+alter table url add column og_description text;
+alter table url add column og_image text;
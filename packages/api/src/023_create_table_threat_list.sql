@@ -0,0 +1,14 @@
+create table if not exists threat_list (
+    id blob not null,
+
+    threat_type text not null,
+    platform_type text not null,
+    threat_entry_type text not null,
+    client_state text,
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (id),
+    unique (threat_type, platform_type, threat_entry_type)
+) strict;
@@ -0,0 +1,9 @@
+create table if not exists threat_hash_prefix (
+    threat_list_id blob not null,
+    prefix blob not null,
+
+    primary key (threat_list_id, prefix),
+    foreign key (threat_list_id) references threat_list(id) on delete cascade on update cascade
+) strict;
+
+create index if not exists threat_hash_prefix_prefix on threat_hash_prefix (prefix);
@@ -0,0 +1,2 @@
+alter table user add column admin integer not null default false;
+alter table user add column banned_at integer;
@@ -0,0 +1,2 @@
+alter table url add column disabled_at integer;
+alter table url add column disabled_reason text;
@@ -0,0 +1,18 @@
+create table if not exists url_report (
+    id blob not null,
+    url_id blob not null,
+
+    reason text not null check (reason in ('phishing', 'malware', 'spam', 'illegal', 'other')),
+    details text,
+    reporter_email text,
+    req_client_ip text,
+    resolved_at integer,
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (id),
+    foreign key (url_id) references url(id) on delete cascade on update cascade
+) strict;
+
+create index if not exists url_report_url_id on url_report (url_id);
@@ -0,0 +1,4 @@
+alter table url add column notes text;
+alter table url add column folder text;
+
+create index if not exists url_folder on url (folder);
@@ -0,0 +1,15 @@
+create table if not exists url_tag (
+    id blob not null,
+    url_id blob not null,
+
+    tag text not null,
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (id),
+    unique (url_id, tag),
+    foreign key (url_id) references url(id) on delete cascade on update cascade
+) strict;
+
+create index if not exists url_tag_tag on url_tag (tag);
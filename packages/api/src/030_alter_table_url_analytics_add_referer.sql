@@ -0,0 +1,3 @@
+alter table url_analytics add column req_referer text;
+
+create index if not exists url_analytics_url_id_created_at on url_analytics (url_id, created_at);
@@ -0,0 +1,6 @@
+alter table url_analytics add column req_accept_language text;
+alter table url_analytics add column req_host text;
+alter table url_analytics add column req_query text;
+alter table url_analytics add column req_device text;
+alter table url_analytics add column req_browser text;
+alter table url_analytics add column req_os text;
@@ -0,0 +1,5 @@
+update url_report set req_client_ip = case
+    when req_client_ip like '[%' then substr(req_client_ip, 2, instr(req_client_ip, ']') - 2)
+    when length(req_client_ip) - length(replace(req_client_ip, ':', '')) = 1 then substr(req_client_ip, 1, instr(req_client_ip, ':') - 1)
+    else req_client_ip
+end;
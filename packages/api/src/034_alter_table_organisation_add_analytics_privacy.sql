@@ -0,0 +1,3 @@
+alter table organisation add column analytics_ip_mode text check (analytics_ip_mode in ('raw', 'truncated', 'hashed'));
+alter table organisation add column analytics_retention_days integer check (analytics_retention_days >= 1);
+alter table organisation add column analytics_retention_action text check (analytics_retention_action in ('delete', 'rollup'));
@@ -0,0 +1 @@
+alter table url add column purged_clicks integer not null default 0;
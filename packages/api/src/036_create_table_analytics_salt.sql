@@ -0,0 +1,10 @@
+create table if not exists analytics_salt (
+    day integer not null,
+
+    salt blob not null,
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (day)
+) strict;
@@ -0,0 +1,13 @@
+create table if not exists url_analytics_rollup (
+    url_id blob not null,
+    day integer not null,
+
+    clicks integer not null,
+    unique_clicks integer not null,
+
+    created_at integer not null,
+    updated_at integer not null,
+
+    primary key (url_id, day),
+    foreign key (url_id) references url(id) on delete cascade on update cascade
+) strict;
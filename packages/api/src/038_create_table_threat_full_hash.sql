@@ -0,0 +1,7 @@
+create table if not exists threat_full_hash (
+    threat_list_id blob not null,
+    hash blob not null,
+
+    primary key (threat_list_id, hash),
+    foreign key (threat_list_id) references threat_list(id) on delete cascade on update cascade
+) strict;
@@ -2,23 +2,42 @@ use {
     axum::{extract::MatchedPath, http::Request},
     axum_login::AuthManagerLayerBuilder,
     std::{env, net::SocketAddr},
-    tokio::net::TcpListener,
+    tokio::{net::TcpListener, signal},
     tower_http::trace::TraceLayer,
     tower_sessions::SessionManagerLayer,
     tracing::info_span,
     tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt},
     util_app_state::AppState,
+    util_click_recorder::ClickRecorder,
     util_https::{serve_http, serve_https, InsecureCertificateResolver},
 };
 
 mod routes;
+mod store_analytics;
+mod store_link;
+mod store_organisation;
+mod store_report;
+mod store_threat_list;
 mod store_user;
 mod util_app_error;
 mod util_app_state;
 mod util_auth;
+mod util_click_recorder;
+mod util_client_ip;
+mod util_geoip;
+mod util_html;
 mod util_https;
+mod util_key_policy;
+mod util_privacy;
+mod util_qr;
+mod util_retention;
 mod util_session;
+mod util_threat_list;
 mod util_token;
+mod util_url;
+mod util_url_policy;
+mod util_user_agent;
+mod util_utm;
 mod util_uuid;
 
 #[tokio::main]
@@ -36,38 +55,76 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let app_state = AppState::new().await;
+    let (click_recorder, click_receiver) = ClickRecorder::from_env();
+    let app_state = AppState::new(click_recorder).await;
+
+    tokio::spawn(util_retention::run(app_state.clone()));
+
+    let click_flusher = tokio::spawn(util_click_recorder::run(app_state.clone(), click_receiver));
 
     let session_layer = SessionManagerLayer::new(app_state.clone())
         .with_same_site(tower_sessions::cookie::SameSite::None);
 
     let auth_layer = AuthManagerLayerBuilder::new(app_state.clone(), session_layer).build();
 
-    tokio::try_join!(
-        serve_http(
-            TcpListener::bind("127.0.0.1:8080").await.unwrap(),
-            routes::AppRouter::http()
-                .with_state(app_state.clone())
-                .into_make_service_with_connect_info::<SocketAddr>(),
-        ),
-        serve_https(
-            TcpListener::bind("127.0.0.1:3000").await.unwrap(),
-            routes::AppRouter::https()
-                .layer(auth_layer)
-                .layer(
-                    TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
-                        let matched_path = request
-                            .extensions()
-                            .get::<MatchedPath>()
-                            .map(MatchedPath::as_str);
+    let servers = async {
+        tokio::try_join!(
+            serve_http(
+                TcpListener::bind("127.0.0.1:8080").await.unwrap(),
+                routes::AppRouter::http()
+                    .with_state(app_state.clone())
+                    .into_make_service_with_connect_info::<SocketAddr>(),
+            ),
+            serve_https(
+                TcpListener::bind("127.0.0.1:3000").await.unwrap(),
+                routes::AppRouter::https()
+                    .layer(auth_layer)
+                    .layer(
+                        TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
+                            let matched_path = request
+                                .extensions()
+                                .get::<MatchedPath>()
+                                .map(MatchedPath::as_str);
 
-                        info_span!("http_request", method = ?request.method(), matched_path)
-                    }),
-                )
-                .with_state(app_state)
-                .into_make_service_with_connect_info::<SocketAddr>(),
-            InsecureCertificateResolver::new(),
+                            info_span!("http_request", method = ?request.method(), matched_path)
+                        }),
+                    )
+                    .with_state(app_state.clone())
+                    .into_make_service_with_connect_info::<SocketAddr>(),
+                InsecureCertificateResolver::new(),
+            )
         )
-    )
-    .unwrap();
+    };
+
+    tokio::select! {
+        result = servers => {
+            result.unwrap();
+        }
+        _ = shutdown_signal() => {}
+    }
+
+    app_state.clicks.shutdown();
+    click_flusher.await.unwrap();
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c().await.unwrap();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .unwrap()
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
@@ -3,25 +3,43 @@ use {
     axum::{
         body::Body,
         response::IntoResponse,
-        routing::{get, post},
+        routing::{delete, get, patch, post, put},
         Router,
     },
     hyper::{header, StatusCode},
     serde::{Deserialize, Serialize},
+    std::collections::HashMap,
     tower_sessions::Session,
+    uuid::Uuid,
 };
 
+mod admin_links_key_disabled;
+mod admin_reports;
+mod admin_reports_id_resolved;
+mod admin_users_id_banned;
 mod api;
 #[cfg(feature = "app_external")]
 mod app_external;
 #[cfg(feature = "app_internal")]
 mod app_internal;
 mod http_to_https_redirect;
+mod key_disabled;
+mod key_preview;
+mod key_social_card;
+mod key_warning;
+mod links;
+mod links_key;
+mod links_key_analytics;
+mod links_key_qr;
 mod me;
+mod organisations_id_privacy;
+mod organisations_id_utm;
+mod report_key;
 mod sign_in;
 mod sign_out;
 mod sign_up;
 mod sign_up_token;
+mod unlock_key;
 mod well_known_acme_challenge;
 mod well_known_openapi_json;
 
@@ -42,11 +60,18 @@ mod app {
 
 pub struct AppRouter {}
 
+pub const RESERVED_KEYS: &[&str] = &["_session", ".well-known", "api", "app"];
+
 const COUNTER_KEY: &str = "counter";
 
 #[derive(Default, Deserialize, Serialize)]
 struct Counter(usize);
 
+const UNLOCKED_LINKS_KEY: &str = "unlocked_link_passwords";
+
+#[derive(Default, Deserialize, Serialize)]
+struct UnlockedLinks(HashMap<Uuid, String>);
+
 impl AppRouter {
     pub fn http() -> Router<AppState> {
         Router::new()
@@ -81,9 +106,93 @@ impl AppRouter {
             .route("/api/sign-out", post(sign_out::post))
             .route("/api/sign-up", post(sign_up::post))
             .route("/api/sign-up/:token", post(sign_up_token::post))
+            .route("/api/links", get(links::get))
+            .route("/api/links/:key", get(links_key::get))
+            .route("/api/links/:key", patch(links_key::patch))
+            .route("/api/links/:key", delete(links_key::delete))
+            .route("/api/links/:key/analytics", get(links_key_analytics::get))
+            .route("/api/links/:key/qr", get(links_key_qr::get))
+            .route("/api/unlock/:key", post(unlock_key::post))
+            .route("/api/report/:key", post(report_key::post))
+            .route("/api/admin/reports", get(admin_reports::get))
+            .route(
+                "/api/admin/reports/:report_id/resolved",
+                put(admin_reports_id_resolved::put),
+            )
+            .route(
+                "/api/admin/reports/:report_id/resolved",
+                delete(admin_reports_id_resolved::delete),
+            )
+            .route(
+                "/api/admin/links/:key/disabled",
+                put(admin_links_key_disabled::put),
+            )
+            .route(
+                "/api/admin/links/:key/disabled",
+                delete(admin_links_key_disabled::delete),
+            )
+            .route(
+                "/api/admin/users/:user_id/banned",
+                put(admin_users_id_banned::put),
+            )
+            .route(
+                "/api/admin/users/:user_id/banned",
+                delete(admin_users_id_banned::delete),
+            )
+            .route(
+                "/api/organisations/:organisation_id/privacy",
+                get(organisations_id_privacy::get),
+            )
+            .route(
+                "/api/organisations/:organisation_id/privacy",
+                put(organisations_id_privacy::put),
+            )
+            .route(
+                "/api/organisations/:organisation_id/utm",
+                get(organisations_id_utm::get),
+            )
+            .route(
+                "/api/organisations/:organisation_id/utm",
+                put(organisations_id_utm::put),
+            )
             .route("/", post(api::post))
             .route("/:key", post(api::post))
             .route("/:key", get(api::get))
+            .route("/:key/*path", get(api::get))
             .nest("/", app::router())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::util_key_policy::KeyPolicy};
+
+    #[test]
+    fn reserves_every_top_level_route() {
+        let policy = KeyPolicy::from_env(RESERVED_KEYS);
+
+        for path in [
+            "/_session",
+            "/.well-known/acme-challenge",
+            "/.well-known/openapi.json",
+            "/api/@me",
+            "/api/links/key/analytics",
+            "/api/admin/reports/id/resolved",
+            "/app",
+            "/app/",
+            "/app/sign-in",
+            "/app/_app/immutable/entry/start.js",
+        ] {
+            let key = path.trim_start_matches('/').split('/').next().unwrap();
+
+            for key in [key.to_string(), key.to_uppercase()] {
+                assert!(
+                    policy.validate(&key).is_err(),
+                    "{path} is routed but {key} can be used as a key"
+                );
+            }
+        }
+
+        assert!(policy.validate("links").is_ok());
+    }
+}
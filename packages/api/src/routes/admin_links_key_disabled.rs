@@ -0,0 +1,76 @@
+use {
+    super::AppState,
+    crate::{
+        store_link::LinkStoreExt, store_report::ReportStoreExt, util_app_error::AppError,
+        util_auth::Admin,
+    },
+    axum::{
+        extract::{Path, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    hyper::StatusCode,
+    serde::Deserialize,
+    utoipa::ToSchema,
+};
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DisableLink {
+    pub reason: Option<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/links/{key}/disabled",
+    operation_id = "disable_link",
+    tag = "moderation",
+    request_body(content = Option<DisableLink>),
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 403),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn put(
+    State(state): State<AppState>,
+    _: Admin,
+    Path(key): Path<String>,
+    disable: Option<Json<DisableLink>>,
+) -> Result<Response, AppError> {
+    let Json(disable) = disable.unwrap_or_default();
+
+    if !state.disable_link(&key, disable.reason).await? {
+        return Ok((StatusCode::NOT_FOUND).into_response());
+    }
+
+    state.resolve_reports(&key).await?;
+
+    Ok((StatusCode::NO_CONTENT).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/links/{key}/disabled",
+    operation_id = "enable_link",
+    tag = "moderation",
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 403),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    _: Admin,
+    Path(key): Path<String>,
+) -> Result<Response, AppError> {
+    if state.enable_link(&key).await? {
+        Ok((StatusCode::NO_CONTENT).into_response())
+    } else {
+        Ok((StatusCode::NOT_FOUND).into_response())
+    }
+}
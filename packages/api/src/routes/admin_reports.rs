@@ -0,0 +1,49 @@
+use {
+    super::AppState,
+    crate::{
+        store_report::{ReportPage, ReportStoreExt},
+        util_app_error::AppError,
+        util_auth::Admin,
+    },
+    axum::{
+        extract::{Query, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    serde::Deserialize,
+    utoipa::IntoParams,
+    uuid::Uuid,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReportsQuery {
+    resolved: Option<bool>,
+    cursor: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/reports",
+    operation_id = "list_reports",
+    tag = "moderation",
+    params(ReportsQuery),
+    responses(
+        (status = 200, body = ReportPage),
+        (status = 401),
+        (status = 403),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    _: Admin,
+    Query(query): Query<ReportsQuery>,
+) -> Result<Response, AppError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let page = state
+        .list_reports(query.resolved.unwrap_or(false), query.cursor, limit)
+        .await?;
+
+    Ok(Json(page).into_response())
+}
@@ -0,0 +1,60 @@
+use {
+    super::AppState,
+    crate::{store_report::ReportStoreExt, util_app_error::AppError, util_auth::Admin},
+    axum::{
+        extract::{Path, State},
+        response::{IntoResponse, Response},
+    },
+    hyper::StatusCode,
+    uuid::Uuid,
+};
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/reports/{report_id}/resolved",
+    operation_id = "resolve_report",
+    tag = "moderation",
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 403),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn put(
+    State(state): State<AppState>,
+    _: Admin,
+    Path(report_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    if state.set_report_resolved(&report_id, true).await? {
+        Ok((StatusCode::NO_CONTENT).into_response())
+    } else {
+        Ok((StatusCode::NOT_FOUND).into_response())
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/reports/{report_id}/resolved",
+    operation_id = "reopen_report",
+    tag = "moderation",
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 403),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    _: Admin,
+    Path(report_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    if state.set_report_resolved(&report_id, false).await? {
+        Ok((StatusCode::NO_CONTENT).into_response())
+    } else {
+        Ok((StatusCode::NOT_FOUND).into_response())
+    }
+}
@@ -0,0 +1,64 @@
+use {
+    super::AppState,
+    crate::{store_user::UserStoreExt, util_app_error::AppError, util_auth::Admin},
+    axum::{
+        extract::{Path, State},
+        response::{IntoResponse, Response},
+    },
+    hyper::StatusCode,
+    uuid::Uuid,
+};
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{user_id}/banned",
+    operation_id = "ban_user",
+    tag = "moderation",
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 403),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn put(
+    State(state): State<AppState>,
+    Admin(admin): Admin,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    if admin.id == user_id {
+        return Ok((StatusCode::FORBIDDEN).into_response());
+    }
+
+    if state.set_user_banned(&user_id, true).await? {
+        Ok((StatusCode::NO_CONTENT).into_response())
+    } else {
+        Ok((StatusCode::NOT_FOUND).into_response())
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{user_id}/banned",
+    operation_id = "unban_user",
+    tag = "moderation",
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 403),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    _: Admin,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    if state.set_user_banned(&user_id, false).await? {
+        Ok((StatusCode::NO_CONTENT).into_response())
+    } else {
+        Ok((StatusCode::NOT_FOUND).into_response())
+    }
+}
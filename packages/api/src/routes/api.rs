@@ -1,81 +1,164 @@
 use {
-    super::AppState,
+    super::{
+        key_disabled, key_preview,
+        key_social_card::{self, SocialCard},
+        key_warning, unlock_key, AppState, UnlockedLinks, UNLOCKED_LINKS_KEY,
+    },
     crate::{
+        store_analytics::{AnalyticsStoreExt, ClickEvent},
+        store_link::{LinkError, LinkStoreExt, LinkVariant, NewLink},
+        store_organisation::OrganisationStoreExt,
+        store_threat_list::ThreatListStoreExt,
         util_app_error::{AppError, InternalServerError},
+        util_client_ip::ClientIp,
+        util_privacy::{anonymise_ip, IpMode},
+        util_url::merge_query_pairs,
+        util_user_agent::{browser, device, is_social_crawler, os, Platform},
+        util_utm::UtmParameters,
         util_uuid::uuid_and_ts,
     },
     axum::{
-        extract::{ConnectInfo, Host, Path, State},
-        http::StatusCode,
+        extract::{Host, Path, Query, RawQuery, State},
+        http::{header, HeaderMap, Method, StatusCode, Uri},
         response::{IntoResponse, Response},
-        Json,
     },
     axum_extra::{extract::OptionalPath, headers::UserAgent, TypedHeader},
     axum_login::AuthSession,
-    nanoid::nanoid,
-    serde_json::json,
-    std::net::SocketAddr,
-    url::Url,
+    chrono::{DateTime, Utc},
+    rand::{thread_rng, Rng},
+    serde::Deserialize,
+    sqlx::types::Json as SqlJson,
+    tower_sessions::Session,
+    url::{form_urlencoded, Url},
+    utoipa::IntoParams,
     uuid::Uuid,
 };
 
+const KEY_GENERATION_ATTEMPTS: usize = 16;
+
+#[derive(Debug, Deserialize)]
+pub struct KeyPath {
+    key: String,
+    path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct NewUrlQuery {
+    organisation_id: Option<Uuid>,
+    utm_source: Option<String>,
+    utm_medium: Option<String>,
+    utm_campaign: Option<String>,
+    utm_term: Option<String>,
+    utm_content: Option<String>,
+}
+
 #[utoipa::path(
   post,
   path = "/",
+  params(NewUrlQuery),
   request_body = String,
   responses(
       (status = 201, body = String),
-      (status = 422, body = String),
-      (status = 500, body = String)
+      (status = 401),
+      (status = 403),
+      (status = 409, body = LinkError),
+      (status = 422, body = LinkError),
+      (status = 500, body = LinkError)
   )
 )]
 pub async fn post(
     State(state): State<AppState>,
     Host(host): Host,
     OptionalPath(path): OptionalPath<String>,
+    Query(query): Query<NewUrlQuery>,
     auth_session: AuthSession<AppState>,
     payload: String,
-) -> Result<Response, AppError> {
-    let _user_id = match auth_session.user {
+) -> Result<Response, LinkError> {
+    let user_id = match auth_session.user {
         Some(user) => user.id,
         None => {
             return Ok((StatusCode::UNAUTHORIZED).into_response());
         }
     };
 
-    let (id, now_ts) = uuid_and_ts();
-    let now_ms = now_ts.timestamp_millis();
-    let key = path.unwrap_or_else(|| nanoid!(8));
-
-    let url_string = match Url::parse(&payload) {
-        Ok(url) => url.to_string(),
-        Err(error) => {
-            return Ok((
-                StatusCode::UNPROCESSABLE_ENTITY,
-                Json(json!({ "error": error.to_string() })),
-            )
-                .into_response());
+    let utm_defaults = match query.organisation_id {
+        Some(organisation_id) => {
+            if !state
+                .is_organisation_member(&organisation_id, &user_id)
+                .await?
+            {
+                return Ok((StatusCode::FORBIDDEN).into_response());
+            }
+
+            state.get_organisation_utm(&organisation_id).await?
         }
+        None => UtmParameters::default(),
     };
 
-    sqlx::query!(
-        r#"
-        insert into url (id, key, url, created_at, updated_at) values (?, ?, ?, ?, ?)
-    "#,
-        id,
-        key,
-        url_string,
-        now_ms,
-        now_ms,
-    )
-    .execute(&state.conn)
-    .await
-    .map_err(anyhow::Error::new)
-    .map_err(InternalServerError)?;
+    let utm = UtmParameters {
+        utm_source: query.utm_source,
+        utm_medium: query.utm_medium,
+        utm_campaign: query.utm_campaign,
+        utm_term: query.utm_term,
+        utm_content: query.utm_content,
+    };
+
+    let mut url = Url::parse(&payload).map_err(|error| LinkError::InvalidUrl {
+        reason: error.to_string(),
+    })?;
+
+    state.url_policy.check(&url, &host)?;
+
+    if let Some(threat_type) = state.find_threat(&url).await? {
+        return Err(LinkError::UrlThreat { threat_type });
+    }
+
+    utm.apply(&mut url);
+    utm_defaults.apply_defaults(&mut url);
+
+    let mut link = NewLink {
+        key: String::new(),
+        url: url.to_string(),
+        user_id,
+        organisation_id: query.organisation_id,
+    };
+
+    match path {
+        Some(key) => {
+            state.key_policy.validate(&key)?;
+            link.key = key;
+
+            if !state.try_create_link(&link).await? {
+                return Err(LinkError::KeyTaken);
+            }
+        }
+        None => {
+            let mut attempts = 0;
+
+            loop {
+                link.key = state.key_policy.generate();
+
+                if state.key_policy.validate(&link.key).is_ok()
+                    && state.try_create_link(&link).await?
+                {
+                    break;
+                }
+
+                attempts += 1;
+
+                if attempts >= KEY_GENERATION_ATTEMPTS {
+                    return Err(anyhow::anyhow!(
+                        "failed to generate a unique key after {attempts} attempts"
+                    )
+                    .into());
+                }
+            }
+        }
+    }
 
     Ok((
         StatusCode::CREATED,
-        Url::parse(&format!("https://{host}:3000/{key}"))
+        short_url(&host, &link.key)
             .map_err(anyhow::Error::new)
             .map_err(InternalServerError)?
             .to_string(),
@@ -83,25 +166,96 @@ pub async fn post(
         .into_response())
 }
 
+pub fn short_url(host: &str, key: &str) -> Result<Url, url::ParseError> {
+    Url::parse(&format!("https://{host}:3000/{key}"))
+}
+
 #[utoipa::path(
-    get,
+    method(get, head),
     path = "/{key}",
     responses(
-        (status = 307),
+        (status = 200, description = "Unlock form for a password protected link, a preview page when the key is suffixed with +, an Open Graph card for social crawlers, or a warning page when the destination is on a threat list", content_type = "text/html"),
+        (status = 301, description = "Permanent redirect", headers(("Location" = String))),
+        (status = 302, description = "Found", headers(("Location" = String))),
+        (status = 307, description = "Temporary redirect, or the fallback URL of an expired link", headers(("Location" = String))),
+        (status = 308, description = "Permanent redirect", headers(("Location" = String))),
         (status = 404),
+        (status = 410, description = "Expired, or disabled by a moderator", content_type = "text/html"),
         (status = 500, body = String),
     )
 )]
 pub async fn get(
     State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Host(host): Host,
+    ClientIp(client_ip): ClientIp,
     TypedHeader(user_agent): TypedHeader<UserAgent>,
-    Path(key): Path<String>,
+    headers: HeaderMap,
+    session: Session,
+    method: Method,
+    uri: Uri,
+    Path(KeyPath { key, path }): Path<KeyPath>,
+    RawQuery(query): RawQuery,
 ) -> Result<Response, AppError> {
+    let (key, preview) = match key.strip_suffix('+') {
+        Some(key) => (key.to_string(), true),
+        None => (key, false),
+    };
+
+    let (id, now_ts) = uuid_and_ts();
+    let now_ms = now_ts.timestamp_millis();
+    let req_country = state.geoip.country(client_ip);
+
     let row = sqlx::query!(
         r#"
-            select id as "id: Uuid", url from url where key = ?
+            select
+                id as "id: Uuid",
+                url,
+                expires_at,
+                max_clicks,
+                purged_clicks,
+                fallback_url,
+                password,
+                redirect_status,
+                forward_query as "forward_query: bool",
+                forward_path as "forward_path: bool",
+                ios_url,
+                android_url,
+                desktop_url,
+                og_title,
+                og_description,
+                og_image,
+                disabled_at,
+                disabled_reason,
+                (
+                    select url_country.url from url_country
+                    where url_country.url_id = url.id and url_country.country = ?
+                ) as country_url,
+                (
+                    select json_group_array(json_object(
+                        'id', lower(hex(url_variant.id)),
+                        'url', url_variant.url,
+                        'weight', url_variant.weight
+                    ))
+                    from url_variant
+                    where url_variant.url_id = url.id
+                ) as "variants!: SqlJson<Vec<LinkVariant>>",
+                active_from / 1000 as "active_from?: DateTime<Utc>",
+                active_until / 1000 as "active_until?: DateTime<Utc>",
+                (
+                    select url_schedule.url from url_schedule
+                    where url_schedule.url_id = url.id and url_schedule.starts_at <= ?
+                    order by url_schedule.starts_at desc
+                    limit 1
+                ) as scheduled_url,
+                (
+                    select organisation.analytics_ip_mode from organisation
+                    where organisation.id = url.organisation_id
+                ) as "analytics_ip_mode?: IpMode"
+            from url
+            where key = ?
         "#,
+        req_country,
+        now_ms,
         key
     )
     .fetch_optional(&state.conn)
@@ -110,30 +264,257 @@ pub async fn get(
     .map_err(InternalServerError)?;
 
     if let Some(row) = row {
-        let (id, now_ts) = uuid_and_ts();
-        let now_ms = now_ts.timestamp_millis();
+        if row.disabled_at.is_some() {
+            return Ok(key_disabled::get(row.disabled_reason.as_deref()));
+        }
 
-        let req_client_ip = addr.to_string();
-        let req_user_agent = user_agent.to_string();
+        if path.is_some() && !row.forward_path {
+            return Ok((StatusCode::NOT_FOUND).into_response());
+        }
+
+        if row
+            .active_from
+            .is_some_and(|active_from| now_ts < active_from)
+        {
+            return Ok((StatusCode::NOT_FOUND).into_response());
+        }
+
+        if row
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now_ms)
+            || row
+                .active_until
+                .is_some_and(|active_until| active_until <= now_ts)
+        {
+            return Ok(gone(&state, row.fallback_url).await?);
+        }
+
+        if let Some(password_hash) = &row.password {
+            let unlocked_links: UnlockedLinks = session
+                .get(UNLOCKED_LINKS_KEY)
+                .await
+                .map_err(anyhow::Error::new)
+                .map_err(InternalServerError)?
+                .unwrap_or_default();
+
+            if unlocked_links.0.get(&row.id)
+                != Some(&unlock_key::password_fingerprint(password_hash))
+            {
+                let next = uri
+                    .path_and_query()
+                    .map_or(uri.path(), |path_and_query| path_and_query.as_str());
+
+                return Ok(unlock_key::form(StatusCode::OK, &key, next, None));
+            }
+        }
 
-        sqlx::query!(
-            r#"
-                insert into url_analytics (id, url_id, req_client_ip, req_user_agent, created_at, updated_at) values (?, ?, ?, ?, ?, ?)
-            "#,
+        if preview {
+            let device_targeted =
+                row.ios_url.is_some() || row.android_url.is_some() || row.desktop_url.is_some();
+            let destination = match (row.scheduled_url, row.variants.as_slice()) {
+                (Some(url), _) => Some(url),
+                (None, []) => Some(row.url),
+                (None, [variant]) => Some(variant.url.clone()),
+                (None, _) => None,
+            }
+            .filter(|_| !device_targeted);
+
+            return key_preview::get(&state, row.id, &key, destination).await;
+        }
+
+        let targeted_url = match Platform::from_user_agent(user_agent.as_str()) {
+            Platform::Ios => row.ios_url,
+            Platform::Android => row.android_url,
+            Platform::Desktop => row.desktop_url,
+            Platform::Other => None,
+        }
+        .or(row.country_url);
+
+        let (destination, url_variant_id) = match (
+            targeted_url.or(row.scheduled_url),
+            pick_variant(&row.variants),
+        ) {
+            (Some(url), _) => (url, None),
+            (None, Some(variant)) => (variant.url.clone(), Some(variant.id)),
+            (None, None) => (row.url, None),
+        };
+
+        let location = forward(
+            destination,
+            path.as_deref(),
+            query.as_deref().filter(|_| row.forward_query),
+        );
+
+        if let Some(threat_type) = find_location_threat(&state, &location).await? {
+            return Ok(key_warning::get(&threat_type, &location));
+        }
+
+        if is_social_crawler(user_agent.as_str()) {
+            let card = SocialCard {
+                url: short_url(&host, &key)
+                    .map_err(anyhow::Error::new)
+                    .map_err(InternalServerError)?
+                    .to_string(),
+                title: row.og_title,
+                description: row.og_description,
+                image: row.og_image,
+            };
+
+            if !card.is_empty() {
+                return Ok(key_social_card::get(card));
+            }
+        }
+
+        if method == Method::HEAD {
+            let available = sqlx::query!(
+                r#"
+                    select ? is null or (select count(*) from url_analytics where url_id = ?) + ? < ? as "available!: bool"
+                "#,
+                row.max_clicks,
+                row.id,
+                row.purged_clicks,
+                row.max_clicks,
+            )
+            .fetch_one(&state.conn)
+            .await
+            .map_err(anyhow::Error::new)
+            .map_err(InternalServerError)?
+            .available;
+
+            if !available {
+                return Ok(gone(&state, row.fallback_url).await?);
+            }
+
+            return Ok(redirect(row.redirect_status, location));
+        }
+
+        let ip_mode = row
+            .analytics_ip_mode
+            .or(state.privacy.ip_mode)
+            .unwrap_or_default();
+        let req_client_ip =
+            anonymise_ip(&state, ip_mode, client_ip, user_agent.as_str(), now_ts).await?;
+
+        let click = ClickEvent {
             id,
-            row.id,
+            url_id: row.id,
+            url_variant_id,
             req_client_ip,
-            req_user_agent,
-            now_ms,
-            now_ms,
-        )
-        .execute(&state.conn)
-        .await
-        .map_err(anyhow::Error::new)
-        .map_err(InternalServerError)?;
-
-        Ok((StatusCode::TEMPORARY_REDIRECT, [("Location", row.url)]).into_response())
+            req_user_agent: (ip_mode == IpMode::Raw).then(|| user_agent.to_string()),
+            req_referer: headers
+                .get(header::REFERER)
+                .and_then(|referer| referer.to_str().ok())
+                .map(str::to_owned),
+            req_accept_language: headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|accept_language| accept_language.to_str().ok())
+                .map(str::to_owned),
+            req_host: host,
+            req_query: query,
+            req_device: device(user_agent.as_str()),
+            req_browser: browser(user_agent.as_str()),
+            req_os: os(user_agent.as_str()),
+            req_country,
+            created_at: now_ms,
+        };
+
+        if row.max_clicks.is_some() {
+            if !state
+                .record_click(&click, row.max_clicks, row.purged_clicks)
+                .await?
+            {
+                return Ok(gone(&state, row.fallback_url).await?);
+            }
+        } else {
+            state.clicks.record(click);
+        }
+
+        Ok(redirect(row.redirect_status, location))
     } else {
         Ok((StatusCode::NOT_FOUND).into_response())
     }
 }
+
+fn pick_variant(variants: &[LinkVariant]) -> Option<&LinkVariant> {
+    let total_weight = variants.iter().fold(0i64, |total, variant| {
+        total.saturating_add(variant.weight.max(0))
+    });
+
+    if total_weight <= 0 {
+        return None;
+    }
+
+    let mut remaining = thread_rng().gen_range(0..total_weight);
+
+    variants.iter().find(|variant| {
+        if remaining < variant.weight {
+            true
+        } else {
+            remaining -= variant.weight;
+            false
+        }
+    })
+}
+
+fn forward(url: String, path: Option<&str>, query: Option<&str>) -> String {
+    if path.is_none() && query.is_none() {
+        return url;
+    }
+
+    let mut destination = match Url::parse(&url) {
+        Ok(destination) => destination,
+        Err(_) => return url,
+    };
+
+    if let Some(path) = path {
+        if let Ok(mut segments) = destination.path_segments_mut() {
+            segments.pop_if_empty().extend(path.split('/'));
+        }
+    }
+
+    if let Some(query) = query {
+        let incoming: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+
+        merge_query_pairs(&mut destination, incoming);
+    }
+
+    destination.to_string()
+}
+
+fn redirect(redirect_status: i64, location: String) -> Response {
+    let status = u16::try_from(redirect_status)
+        .ok()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .filter(StatusCode::is_redirection)
+        .unwrap_or(StatusCode::TEMPORARY_REDIRECT);
+
+    (status, [("Location", location)]).into_response()
+}
+
+async fn find_location_threat(
+    state: &AppState,
+    location: &str,
+) -> Result<Option<String>, InternalServerError> {
+    match Url::parse(location) {
+        Ok(url) => state.find_threat(&url).await,
+        Err(_) => Ok(None),
+    }
+}
+
+async fn gone(
+    state: &AppState,
+    fallback_url: Option<String>,
+) -> Result<Response, InternalServerError> {
+    let fallback_url = match fallback_url {
+        Some(fallback_url) => fallback_url,
+        None => return Ok((StatusCode::GONE).into_response()),
+    };
+
+    if let Some(threat_type) = find_location_threat(state, &fallback_url).await? {
+        return Ok(key_warning::get(&threat_type, &fallback_url));
+    }
+
+    Ok((StatusCode::TEMPORARY_REDIRECT, [("Location", fallback_url)]).into_response())
+}
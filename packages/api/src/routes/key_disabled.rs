@@ -0,0 +1,20 @@
+use {
+    crate::util_html::{escape, page},
+    axum::response::Response,
+    hyper::StatusCode,
+};
+
+pub fn get(disabled_reason: Option<&str>) -> Response {
+    let reason = disabled_reason.map_or(String::new(), |reason| {
+        format!("<p>Reason: {}</p>", escape(reason))
+    });
+
+    page(
+        StatusCode::GONE,
+        "This link has been disabled",
+        &format!(
+            r#"<p>This link was disabled by a moderator and no longer redirects.</p>
+            {reason}"#
+        ),
+    )
+}
@@ -0,0 +1,73 @@
+use {
+    super::AppState,
+    crate::{
+        util_app_error::{AppError, InternalServerError},
+        util_html::{escape, page},
+    },
+    axum::response::{IntoResponse, Response},
+    chrono::DateTime,
+    hyper::StatusCode,
+    uuid::Uuid,
+};
+
+pub async fn get(
+    state: &AppState,
+    url_id: Uuid,
+    key: &str,
+    destination: Option<String>,
+) -> Result<Response, AppError> {
+    let row = sqlx::query!(
+        r#"
+            select
+                created_at,
+                (
+                    select count(*) from url_analytics
+                    where url_analytics.url_id = url.id
+                ) + purged_clicks as "clicks!: i64",
+                exists(
+                    select 1 from url_country where url_country.url_id = url.id
+                ) as "country_targeted!: bool"
+            from url
+            where id = ?
+        "#,
+        url_id
+    )
+    .fetch_optional(&state.conn)
+    .await
+    .map_err(anyhow::Error::new)
+    .map_err(InternalServerError)?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok((StatusCode::NOT_FOUND).into_response()),
+    };
+
+    let destination = match destination.filter(|_| !row.country_targeted) {
+        Some(destination) => format!("<code>{}</code>", escape(&destination)),
+        None => "<em>Varies by device, country or traffic split</em>".to_string(),
+    };
+
+    let created_at = DateTime::from_timestamp_millis(row.created_at)
+        .map_or(String::new(), |created_at| {
+            created_at.format("%Y-%m-%d %H:%M UTC").to_string()
+        });
+
+    let key = escape(key);
+
+    Ok(page(
+        StatusCode::OK,
+        "Link preview",
+        &format!(
+            r#"<dl>
+                <dt>Destination</dt>
+                <dd>{destination}</dd>
+                <dt>Created</dt>
+                <dd><time>{created_at}</time></dd>
+                <dt>Clicks</dt>
+                <dd>{clicks}</dd>
+            </dl>
+            <p><a href="/{key}" rel="noreferrer">Continue to link</a></p>"#,
+            clicks = row.clicks,
+        ),
+    ))
+}
@@ -0,0 +1,84 @@
+use {
+    crate::util_html::escape,
+    axum::response::{IntoResponse, Response},
+    hyper::{header, StatusCode},
+};
+
+pub struct SocialCard {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+}
+
+impl SocialCard {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.image.is_none()
+    }
+}
+
+pub fn get(card: SocialCard) -> Response {
+    let url = escape(&card.url);
+
+    let mut meta = vec![
+        r#"<meta property="og:type" content="website" />"#.to_string(),
+        format!(r#"<meta property="og:url" content="{url}" />"#),
+        format!(
+            r#"<meta name="twitter:card" content="{}" />"#,
+            if card.image.is_some() {
+                "summary_large_image"
+            } else {
+                "summary"
+            }
+        ),
+    ];
+
+    if let Some(title) = &card.title {
+        let title = escape(title);
+        meta.push(format!(r#"<meta property="og:title" content="{title}" />"#));
+        meta.push(format!(
+            r#"<meta name="twitter:title" content="{title}" />"#
+        ));
+    }
+
+    if let Some(description) = &card.description {
+        let description = escape(description);
+        meta.push(format!(
+            r#"<meta property="og:description" content="{description}" />"#
+        ));
+        meta.push(format!(
+            r#"<meta name="twitter:description" content="{description}" />"#
+        ));
+    }
+
+    if let Some(image) = &card.image {
+        let image = escape(image);
+        meta.push(format!(r#"<meta property="og:image" content="{image}" />"#));
+        meta.push(format!(
+            r#"<meta name="twitter:image" content="{image}" />"#
+        ));
+    }
+
+    let title = escape(card.title.as_deref().unwrap_or("itty.pro"));
+    let meta = meta.join("\n        ");
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+        format!(
+            r#"<!doctype html>
+<html lang="en">
+    <head>
+        <meta charset="utf-8" />
+        <title>{title}</title>
+        {meta}
+    </head>
+    <body>
+        <a href="{url}">{title}</a>
+    </body>
+</html>
+"#
+        ),
+    )
+        .into_response()
+}
@@ -0,0 +1,20 @@
+use {
+    crate::util_html::{escape, page},
+    axum::response::Response,
+    hyper::StatusCode,
+};
+
+pub fn get(threat_type: &str, location: &str) -> Response {
+    let threat_type = escape(&threat_type.replace('_', " ").to_lowercase());
+    let location = escape(location);
+
+    page(
+        StatusCode::OK,
+        "Warning: this link may be unsafe",
+        &format!(
+            r#"<p role="alert">The destination of this link is listed as a known <strong>{threat_type}</strong> threat. Visiting it may put your device or personal information at risk.</p>
+            <p><code>{location}</code></p>
+            <p><a href="{location}" rel="noreferrer nofollow">Continue anyway</a></p>"#
+        ),
+    )
+}
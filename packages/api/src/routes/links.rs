@@ -0,0 +1,56 @@
+use {
+    super::AppState,
+    crate::{
+        store_link::{LinkFilter, LinkPage, LinkStoreExt},
+        util_app_error::AppError,
+    },
+    axum::{
+        extract::{Query, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    axum_login::AuthSession,
+    hyper::StatusCode,
+    serde::Deserialize,
+    utoipa::IntoParams,
+    uuid::Uuid,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LinksQuery {
+    cursor: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/links",
+    operation_id = "list_links",
+    tag = "links",
+    params(LinksQuery, LinkFilter),
+    responses(
+        (status = 200, body = LinkPage),
+        (status = 401),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Query(query): Query<LinksQuery>,
+    Query(filter): Query<LinkFilter>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let page = state
+        .list_links(&user_id, &filter, query.cursor, limit)
+        .await?;
+
+    Ok(Json(page).into_response())
+}
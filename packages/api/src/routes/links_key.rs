@@ -0,0 +1,122 @@
+use {
+    super::AppState,
+    crate::{
+        store_link::{Link, LinkError, LinkStoreExt, LinkUpdate},
+        store_threat_list::ThreatListStoreExt,
+        util_app_error::AppError,
+    },
+    axum::{
+        extract::{Host, Path, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    axum_login::AuthSession,
+    hyper::StatusCode,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{key}",
+    operation_id = "get_link",
+    tag = "links",
+    responses(
+        (status = 200, body = Link),
+        (status = 401),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(key): Path<String>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    match state.get_link(&user_id, &key).await? {
+        Some(link) => Ok(Json(link).into_response()),
+        None => Ok((StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/links/{key}",
+    operation_id = "update_link",
+    tag = "links",
+    request_body = LinkUpdate,
+    responses(
+        (status = 200, body = Link),
+        (status = 401),
+        (status = 404, body = LinkError),
+        (status = 409, body = LinkError),
+        (status = 422, body = LinkError),
+        (status = 500, body = LinkError)
+    )
+)]
+pub async fn patch(
+    State(state): State<AppState>,
+    Host(host): Host,
+    auth_session: AuthSession<AppState>,
+    Path(key): Path<String>,
+    Json(update): Json<LinkUpdate>,
+) -> Result<Response, LinkError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if let Some(new_key) = &update.key {
+        state.key_policy.validate(new_key)?;
+    }
+
+    for destination in update.destinations() {
+        state.url_policy.check(destination, &host)?;
+
+        if let Some(threat_type) = state.find_threat(destination).await? {
+            return Err(LinkError::UrlThreat { threat_type });
+        }
+    }
+
+    let link = state.update_link(&user_id, &key, update).await?;
+
+    Ok(Json(link).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/links/{key}",
+    operation_id = "delete_link",
+    tag = "links",
+    responses(
+        (status = 204),
+        (status = 401),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(key): Path<String>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if state.delete_link(&user_id, &key).await? {
+        Ok((StatusCode::NO_CONTENT).into_response())
+    } else {
+        Ok((StatusCode::NOT_FOUND).into_response())
+    }
+}
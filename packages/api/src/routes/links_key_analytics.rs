@@ -0,0 +1,51 @@
+use {
+    super::AppState,
+    crate::{
+        store_analytics::{AnalyticsError, AnalyticsQuery, AnalyticsStoreExt, LinkAnalytics},
+        store_link::LinkStoreExt,
+    },
+    axum::{
+        extract::{Path, Query, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    axum_login::AuthSession,
+    hyper::StatusCode,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{key}/analytics",
+    operation_id = "get_link_analytics",
+    tag = "links",
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, body = LinkAnalytics),
+        (status = 401),
+        (status = 404),
+        (status = 422, body = AnalyticsError),
+        (status = 500, body = AnalyticsError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Response, AnalyticsError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    let link = match state.get_link(&user_id, &key).await? {
+        Some(link) => link,
+        None => return Ok((StatusCode::NOT_FOUND).into_response()),
+    };
+
+    let analytics = state.get_link_analytics(&link.id, query).await?;
+
+    Ok(Json(analytics).into_response())
+}
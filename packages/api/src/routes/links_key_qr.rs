@@ -0,0 +1,98 @@
+use {
+    super::{api::short_url, AppState},
+    crate::{
+        store_link::LinkStoreExt,
+        util_qr::{
+            render_png, render_svg, Colour, QrError, QrErrorCorrection, QrFormat, QrOptions,
+        },
+    },
+    axum::{
+        extract::{Host, Path, Query, State},
+        response::{IntoResponse, Response},
+    },
+    axum_login::AuthSession,
+    hyper::{header, StatusCode},
+    serde::Deserialize,
+    utoipa::IntoParams,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct QrQuery {
+    format: Option<QrFormat>,
+    size: Option<u32>,
+    margin: Option<u32>,
+    error_correction: Option<QrErrorCorrection>,
+    foreground: Option<String>,
+    background: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{key}/qr",
+    operation_id = "get_link_qr",
+    tag = "links",
+    params(QrQuery),
+    responses(
+        (status = 200, content((String = "image/svg+xml"), (Vec<u8> = "image/png"))),
+        (status = 401),
+        (status = 404),
+        (status = 422, body = QrError),
+        (status = 500, body = QrError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    Host(host): Host,
+    auth_session: AuthSession<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<QrQuery>,
+) -> Result<Response, QrError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if state
+        .get_link(&user_id, &key)
+        .await
+        .map_err(|err| err.0)?
+        .is_none()
+    {
+        return Ok((StatusCode::NOT_FOUND).into_response());
+    }
+
+    let options = QrOptions {
+        size: query.size.unwrap_or(256).clamp(64, 2048),
+        margin: query.margin.unwrap_or(4).min(16),
+        error_correction: query.error_correction.unwrap_or_default(),
+        foreground: query
+            .foreground
+            .as_deref()
+            .map_or(Ok(Colour::BLACK), Colour::parse)?,
+        background: query
+            .background
+            .as_deref()
+            .map_or(Ok(Colour::WHITE), Colour::parse)?,
+    };
+
+    let data = short_url(&host, &key)
+        .map_err(anyhow::Error::new)?
+        .to_string();
+
+    match query.format.unwrap_or_default() {
+        QrFormat::Svg => Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "image/svg+xml")],
+            render_svg(&data, &options)?,
+        )
+            .into_response()),
+        QrFormat::Png => Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "image/png")],
+            render_png(&data, &options)?,
+        )
+            .into_response()),
+    }
+}
@@ -0,0 +1,101 @@
+use {
+    super::AppState,
+    crate::{
+        store_organisation::OrganisationStoreExt, util_app_error::AppError,
+        util_privacy::PrivacySettings,
+    },
+    axum::{
+        extract::{Path, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    axum_login::AuthSession,
+    hyper::StatusCode,
+    serde_json::json,
+    uuid::Uuid,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/organisations/{organisation_id}/privacy",
+    operation_id = "get_organisation_privacy",
+    tag = "organisations",
+    responses(
+        (status = 200, body = PrivacySettings),
+        (status = 401),
+        (status = 403),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(organisation_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if !state
+        .is_organisation_member(&organisation_id, &user_id)
+        .await?
+    {
+        return Ok((StatusCode::FORBIDDEN).into_response());
+    }
+
+    let privacy = state.get_organisation_privacy(&organisation_id).await?;
+
+    Ok(Json(privacy).into_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/organisations/{organisation_id}/privacy",
+    operation_id = "set_organisation_privacy",
+    tag = "organisations",
+    request_body = PrivacySettings,
+    responses(
+        (status = 200, body = PrivacySettings),
+        (status = 401),
+        (status = 403),
+        (status = 422),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn put(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(organisation_id): Path<Uuid>,
+    Json(privacy): Json<PrivacySettings>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if !state
+        .is_organisation_member(&organisation_id, &user_id)
+        .await?
+    {
+        return Ok((StatusCode::FORBIDDEN).into_response());
+    }
+
+    if !privacy.is_valid() {
+        return Ok((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": "retention_days must be at least 1" })),
+        )
+            .into_response());
+    }
+
+    state
+        .set_organisation_privacy(&organisation_id, &privacy)
+        .await?;
+
+    Ok(Json(privacy).into_response())
+}
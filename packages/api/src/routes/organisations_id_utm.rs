@@ -0,0 +1,88 @@
+use {
+    super::AppState,
+    crate::{
+        store_organisation::OrganisationStoreExt, util_app_error::AppError, util_utm::UtmParameters,
+    },
+    axum::{
+        extract::{Path, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    axum_login::AuthSession,
+    hyper::StatusCode,
+    uuid::Uuid,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/organisations/{organisation_id}/utm",
+    operation_id = "get_organisation_utm",
+    tag = "organisations",
+    responses(
+        (status = 200, body = UtmParameters),
+        (status = 401),
+        (status = 403),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(organisation_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if !state
+        .is_organisation_member(&organisation_id, &user_id)
+        .await?
+    {
+        return Ok((StatusCode::FORBIDDEN).into_response());
+    }
+
+    let utm = state.get_organisation_utm(&organisation_id).await?;
+
+    Ok(Json(utm).into_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/organisations/{organisation_id}/utm",
+    operation_id = "set_organisation_utm",
+    tag = "organisations",
+    request_body = UtmParameters,
+    responses(
+        (status = 200, body = UtmParameters),
+        (status = 401),
+        (status = 403),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn put(
+    State(state): State<AppState>,
+    auth_session: AuthSession<AppState>,
+    Path(organisation_id): Path<Uuid>,
+    Json(utm): Json<UtmParameters>,
+) -> Result<Response, AppError> {
+    let user_id = match auth_session.user {
+        Some(user) => user.id,
+        None => {
+            return Ok((StatusCode::UNAUTHORIZED).into_response());
+        }
+    };
+
+    if !state
+        .is_organisation_member(&organisation_id, &user_id)
+        .await?
+    {
+        return Ok((StatusCode::FORBIDDEN).into_response());
+    }
+
+    state.set_organisation_utm(&organisation_id, &utm).await?;
+
+    Ok(Json(utm).into_response())
+}
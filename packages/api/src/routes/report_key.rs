@@ -0,0 +1,52 @@
+use {
+    super::AppState,
+    crate::{
+        store_organisation::OrganisationStoreExt,
+        store_report::{NewReport, ReportError, ReportStoreExt},
+        util_client_ip::ClientIp,
+        util_privacy::anonymise_ip,
+    },
+    axum::{
+        extract::{Path, State},
+        response::{IntoResponse, Response},
+        Json,
+    },
+    axum_extra::{headers::UserAgent, TypedHeader},
+    chrono::Utc,
+    hyper::StatusCode,
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/report/{key}",
+    operation_id = "report_link",
+    tag = "moderation",
+    request_body = NewReport,
+    responses(
+        (status = 201),
+        (status = 404, body = ReportError),
+        (status = 422, body = ReportError),
+        (status = 500, body = ReportError)
+    )
+)]
+pub async fn post(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    Path(key): Path<String>,
+    Json(report): Json<NewReport>,
+) -> Result<Response, ReportError> {
+    let ip_mode = state
+        .get_link_ip_mode(&key)
+        .await?
+        .or(state.privacy.ip_mode)
+        .unwrap_or_default();
+    let user_agent = user_agent.map_or(String::new(), |TypedHeader(user_agent)| {
+        user_agent.to_string()
+    });
+    let req_client_ip = anonymise_ip(&state, ip_mode, client_ip, &user_agent, Utc::now()).await?;
+
+    state.new_report(&key, report, &req_client_ip).await?;
+
+    Ok((StatusCode::CREATED).into_response())
+}
@@ -0,0 +1,145 @@
+use {
+    super::{AppState, UnlockedLinks, UNLOCKED_LINKS_KEY},
+    crate::{
+        util_app_error::{AppError, InternalServerError},
+        util_html::{escape, page},
+    },
+    axum::{
+        extract::{Path, State},
+        http::Uri,
+        response::{IntoResponse, Redirect, Response},
+        Form,
+    },
+    hyper::StatusCode,
+    password_auth::verify_password,
+    serde::Deserialize,
+    sha2::{Digest, Sha256},
+    tokio::task,
+    tower_sessions::Session,
+    utoipa::ToSchema,
+    uuid::Uuid,
+    veil::Redact,
+};
+
+#[derive(Redact, Deserialize, ToSchema)]
+pub struct UnlockCredentials {
+    #[redact]
+    pub password: String,
+    pub next: Option<String>,
+}
+
+pub fn password_fingerprint(password_hash: &str) -> String {
+    Sha256::digest(password_hash.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn is_link_path(next: &str, key: &str) -> bool {
+    next.parse::<Uri>().is_ok()
+        && next
+            .strip_prefix('/')
+            .and_then(|next| next.strip_prefix(key))
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?', '+']))
+}
+
+pub fn form(status: StatusCode, key: &str, next: &str, error: Option<&str>) -> Response {
+    let key = escape(key);
+    let next = escape(next);
+    let error = error.map_or(String::new(), |error| {
+        format!(r#"<p role="alert">{}</p>"#, escape(error))
+    });
+
+    page(
+        status,
+        "This link is password protected",
+        &format!(
+            r#"{error}
+            <form method="post" action="/api/unlock/{key}">
+                <label>
+                    Password
+                    <input type="password" name="password" autocomplete="off" autofocus required />
+                </label>
+                <input type="hidden" name="next" value="{next}" />
+                <button type="submit">Unlock</button>
+            </form>"#
+        ),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/unlock/{key}",
+    operation_id = "unlock_link",
+    tag = "links",
+    request_body(content = UnlockCredentials, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303),
+        (status = 401, content_type = "text/html"),
+        (status = 404),
+        (status = 500, body = AppError)
+    )
+)]
+pub async fn post(
+    State(state): State<AppState>,
+    session: Session,
+    Path(key): Path<String>,
+    Form(credentials): Form<UnlockCredentials>,
+) -> Result<Response, AppError> {
+    let row = sqlx::query!(
+        r#"
+            select id as "id: Uuid", password from url where key = ?
+        "#,
+        key
+    )
+    .fetch_optional(&state.conn)
+    .await
+    .map_err(anyhow::Error::new)
+    .map_err(InternalServerError)?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok((StatusCode::NOT_FOUND).into_response()),
+    };
+
+    let next = credentials
+        .next
+        .filter(|next| is_link_path(next, &key))
+        .unwrap_or_else(|| format!("/{key}"));
+
+    if let Some(password_hash) = row.password {
+        let fingerprint = password_fingerprint(&password_hash);
+        let verified = task::spawn_blocking(move || {
+            verify_password(credentials.password, &password_hash).is_ok()
+        })
+        .await
+        .map_err(anyhow::Error::new)
+        .map_err(InternalServerError)?;
+
+        if !verified {
+            return Ok(form(
+                StatusCode::UNAUTHORIZED,
+                &key,
+                &next,
+                Some("Incorrect password"),
+            ));
+        }
+
+        let mut unlocked_links: UnlockedLinks = session
+            .get(UNLOCKED_LINKS_KEY)
+            .await
+            .map_err(anyhow::Error::new)
+            .map_err(InternalServerError)?
+            .unwrap_or_default();
+
+        unlocked_links.0.insert(row.id, fingerprint);
+
+        session
+            .insert(UNLOCKED_LINKS_KEY, unlocked_links)
+            .await
+            .map_err(anyhow::Error::new)
+            .map_err(InternalServerError)?;
+    }
+
+    Ok(Redirect::to(&next).into_response())
+}
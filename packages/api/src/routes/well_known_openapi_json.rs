@@ -1,5 +1,9 @@
 use {
-    super::{api, me, sign_in, sign_out, sign_up, sign_up_token},
+    super::{
+        admin_links_key_disabled, admin_reports, admin_reports_id_resolved, admin_users_id_banned,
+        api, links, links_key, links_key_analytics, links_key_qr, me, organisations_id_privacy,
+        organisations_id_utm, report_key, sign_in, sign_out, sign_up, sign_up_token, unlock_key,
+    },
     axum::{
         response::{IntoResponse, Response},
         Json,
@@ -9,13 +13,32 @@ use {
 
 #[derive(OpenApi)]
 #[openapi(paths(
+    admin_links_key_disabled::put,
+    admin_links_key_disabled::delete,
+    admin_reports::get,
+    admin_reports_id_resolved::put,
+    admin_reports_id_resolved::delete,
+    admin_users_id_banned::put,
+    admin_users_id_banned::delete,
     api::post,
     api::get,
+    links::get,
+    links_key::get,
+    links_key::patch,
+    links_key::delete,
+    links_key_analytics::get,
+    links_key_qr::get,
     me::get,
+    organisations_id_privacy::get,
+    organisations_id_privacy::put,
+    organisations_id_utm::get,
+    organisations_id_utm::put,
+    report_key::post,
     sign_in::post,
     sign_out::post,
     sign_up::post,
     sign_up_token::post,
+    unlock_key::post,
 ))]
 pub struct OpenApiSchema;
 
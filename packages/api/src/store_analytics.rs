@@ -0,0 +1,727 @@
+use {
+    crate::{
+        util_app_error::InternalServerError,
+        util_app_state::Database,
+        util_privacy::{PrivacySettings, RetentionAction},
+        util_user_agent::browser,
+    },
+    axum::{response::IntoResponse, Json},
+    chrono::{DateTime, Duration, Utc},
+    hyper::StatusCode,
+    serde::{Deserialize, Serialize},
+    sqlx::SqliteExecutor,
+    std::collections::HashMap,
+    thiserror::Error,
+    url::Url,
+    utoipa::{IntoParams, ToSchema},
+    uuid::Uuid,
+};
+
+const MAX_BUCKETS: i64 = 10_000;
+const MAX_BREAKDOWN_ENTRIES: usize = 50;
+const DIRECT_REFERRER: &str = "direct";
+const DAY_MS: i64 = 86_400_000;
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsInterval {
+    Hour,
+    #[default]
+    Day,
+    Week,
+}
+
+impl AnalyticsInterval {
+    fn size_ms(&self) -> i64 {
+        match self {
+            AnalyticsInterval::Hour => Duration::hours(1).num_milliseconds(),
+            AnalyticsInterval::Day => Duration::days(1).num_milliseconds(),
+            AnalyticsInterval::Week => Duration::weeks(1).num_milliseconds(),
+        }
+    }
+
+    fn offset_ms(&self) -> i64 {
+        match self {
+            AnalyticsInterval::Week => -Duration::days(3).num_milliseconds(),
+            _ => 0,
+        }
+    }
+
+    fn bucket(&self, ts_ms: i64) -> i64 {
+        (ts_ms - self.offset_ms()).div_euclid(self.size_ms()) * self.size_ms() + self.offset_ms()
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AnalyticsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub interval: Option<AnalyticsInterval>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnalyticsBucket {
+    pub starts_at: DateTime<Utc>,
+    pub clicks: i64,
+    pub unique_clicks: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnalyticsBreakdown {
+    pub value: String,
+    pub clicks: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkAnalytics {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub interval: AnalyticsInterval,
+    pub total_clicks: i64,
+    pub unique_clicks: i64,
+    pub series: Vec<AnalyticsBucket>,
+    pub user_agents: Vec<AnalyticsBreakdown>,
+    pub referrers: Vec<AnalyticsBreakdown>,
+}
+
+#[derive(Error, Debug, Serialize, ToSchema)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum AnalyticsError {
+    #[error("from must be before to")]
+    InvalidRange,
+    #[error("range contains too many buckets for the interval")]
+    TooManyBuckets { max_buckets: i64 },
+    #[error(transparent)]
+    InternalServerError {
+        #[serde(skip)]
+        #[from]
+        error: anyhow::Error,
+    },
+}
+
+impl From<InternalServerError> for AnalyticsError {
+    fn from(value: InternalServerError) -> Self {
+        Self::InternalServerError { error: value.0 }
+    }
+}
+
+impl Into<StatusCode> for &AnalyticsError {
+    fn into(self) -> StatusCode {
+        match self {
+            AnalyticsError::InvalidRange => StatusCode::UNPROCESSABLE_ENTITY,
+            AnalyticsError::TooManyBuckets { max_buckets: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            AnalyticsError::InternalServerError { error: _ } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for AnalyticsError {
+    fn into_response(self) -> axum::response::Response {
+        (Into::<StatusCode>::into(&self), Json(self)).into_response()
+    }
+}
+
+fn breakdown(counts: HashMap<String, i64>) -> Vec<AnalyticsBreakdown> {
+    let mut breakdown: Vec<AnalyticsBreakdown> = counts
+        .into_iter()
+        .map(|(value, clicks)| AnalyticsBreakdown { value, clicks })
+        .collect();
+
+    breakdown.sort_by(|a, b| b.clicks.cmp(&a.clicks).then_with(|| a.value.cmp(&b.value)));
+    breakdown.truncate(MAX_BREAKDOWN_ENTRIES);
+
+    breakdown
+}
+
+fn referrer_host(referer: Option<String>) -> String {
+    referer
+        .and_then(|referer| Url::parse(&referer).ok())
+        .and_then(|referer| referer.host_str().map(str::to_string))
+        .unwrap_or(DIRECT_REFERRER.to_string())
+}
+
+#[derive(Debug)]
+pub struct ClickEvent {
+    pub id: Uuid,
+    pub url_id: Uuid,
+    pub url_variant_id: Option<Uuid>,
+    pub req_client_ip: String,
+    pub req_user_agent: Option<String>,
+    pub req_referer: Option<String>,
+    pub req_accept_language: Option<String>,
+    pub req_host: String,
+    pub req_query: Option<String>,
+    pub req_device: &'static str,
+    pub req_browser: &'static str,
+    pub req_os: &'static str,
+    pub req_country: Option<String>,
+    pub created_at: i64,
+}
+
+async fn insert_click(
+    conn: impl SqliteExecutor<'_>,
+    click: &ClickEvent,
+    max_clicks: Option<i64>,
+    purged_clicks: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            insert into url_analytics (id, url_id, url_variant_id, req_client_ip, req_user_agent, req_referer, req_accept_language, req_host, req_query, req_device, req_browser, req_os, req_country, created_at, updated_at)
+            select
+                ?,
+                url.id,
+                (
+                    select url_variant.id from url_variant
+                    where url_variant.id = ? and url_variant.url_id = url.id
+                ),
+                ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+            from url
+            where url.id = ?
+            and (? is null or (select count(*) from url_analytics where url_id = url.id) + ? < ?)
+        "#,
+        click.id,
+        click.url_variant_id,
+        click.req_client_ip,
+        click.req_user_agent,
+        click.req_referer,
+        click.req_accept_language,
+        click.req_host,
+        click.req_query,
+        click.req_device,
+        click.req_browser,
+        click.req_os,
+        click.req_country,
+        click.created_at,
+        click.created_at,
+        click.url_id,
+        max_clicks,
+        purged_clicks,
+        max_clicks,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[async_trait::async_trait]
+pub trait AnalyticsStoreExt {
+    async fn record_click(
+        &self,
+        click: &ClickEvent,
+        max_clicks: Option<i64>,
+        purged_clicks: i64,
+    ) -> Result<bool, InternalServerError>;
+    async fn record_clicks(&self, clicks: &[ClickEvent]) -> Result<(), InternalServerError>;
+    async fn get_link_analytics(
+        &self,
+        url_id: &Uuid,
+        query: AnalyticsQuery,
+    ) -> Result<LinkAnalytics, AnalyticsError>;
+    async fn get_analytics_salt(&self, day: i64) -> Result<Vec<u8>, InternalServerError>;
+    async fn apply_retention(&self, defaults: &PrivacySettings) -> Result<(), InternalServerError>;
+}
+
+#[async_trait::async_trait]
+impl<AppState: Database> AnalyticsStoreExt for AppState {
+    async fn record_click(
+        &self,
+        click: &ClickEvent,
+        max_clicks: Option<i64>,
+        purged_clicks: i64,
+    ) -> Result<bool, InternalServerError> {
+        let recorded = insert_click(self.conn(), click, max_clicks, purged_clicks)
+            .await
+            .map_err(anyhow::Error::new)?;
+
+        Ok(recorded)
+    }
+
+    async fn record_clicks(&self, clicks: &[ClickEvent]) -> Result<(), InternalServerError> {
+        let mut tx = self.conn().begin().await.map_err(anyhow::Error::new)?;
+
+        for click in clicks {
+            insert_click(&mut *tx, click, None, 0)
+                .await
+                .map_err(anyhow::Error::new)?;
+        }
+
+        tx.commit().await.map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+
+    async fn get_link_analytics(
+        &self,
+        url_id: &Uuid,
+        query: AnalyticsQuery,
+    ) -> Result<LinkAnalytics, AnalyticsError> {
+        let interval = query.interval.unwrap_or_default();
+        let to = query.to.unwrap_or_else(Utc::now);
+        let from = query.from.unwrap_or(to - Duration::days(30));
+
+        if from >= to {
+            return Err(AnalyticsError::InvalidRange);
+        }
+
+        let from_ms = from.timestamp_millis();
+        let to_ms = to.timestamp_millis();
+        let first_bucket = interval.bucket(from_ms);
+
+        if (to_ms - first_bucket) / interval.size_ms() >= MAX_BUCKETS {
+            return Err(AnalyticsError::TooManyBuckets {
+                max_buckets: MAX_BUCKETS,
+            });
+        }
+
+        let size_ms = interval.size_ms();
+        let offset_ms = interval.offset_ms();
+
+        let totals = sqlx::query!(
+            r#"
+                select
+                    count(*) as "clicks!: i64",
+                    count(distinct req_client_ip) as "unique_clicks!: i64"
+                from url_analytics
+                where url_id = ? and created_at >= ? and created_at < ?
+            "#,
+            url_id,
+            from_ms,
+            to_ms,
+        )
+        .fetch_one(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let buckets = sqlx::query!(
+            r#"
+                select
+                    ((created_at - ?) / ?) * ? + ? as "bucket!: i64",
+                    count(*) as "clicks!: i64",
+                    count(distinct req_client_ip) as "unique_clicks!: i64"
+                from url_analytics
+                where url_id = ? and created_at >= ? and created_at < ?
+                group by 1
+            "#,
+            offset_ms,
+            size_ms,
+            size_ms,
+            offset_ms,
+            url_id,
+            from_ms,
+            to_ms,
+        )
+        .fetch_all(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let rollup_totals = sqlx::query!(
+            r#"
+                select
+                    coalesce(sum(clicks), 0) as "clicks!: i64",
+                    coalesce(sum(unique_clicks), 0) as "unique_clicks!: i64"
+                from url_analytics_rollup
+                where url_id = ? and day >= ? and day < ?
+            "#,
+            url_id,
+            from_ms,
+            to_ms,
+        )
+        .fetch_one(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let rollup_buckets = sqlx::query!(
+            r#"
+                select
+                    ((day - ?) / ?) * ? + ? as "bucket!: i64",
+                    sum(clicks) as "clicks!: i64",
+                    sum(unique_clicks) as "unique_clicks!: i64"
+                from url_analytics_rollup
+                where url_id = ? and day >= ? and day < ?
+                group by 1
+            "#,
+            offset_ms,
+            size_ms,
+            size_ms,
+            offset_ms,
+            url_id,
+            from_ms,
+            to_ms,
+        )
+        .fetch_all(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let mut buckets: HashMap<i64, (i64, i64)> = buckets
+            .into_iter()
+            .map(|row| (row.bucket, (row.clicks, row.unique_clicks)))
+            .collect();
+
+        for row in rollup_buckets {
+            let bucket = buckets.entry(row.bucket).or_default();
+            bucket.0 += row.clicks;
+            bucket.1 += row.unique_clicks;
+        }
+
+        let series = (first_bucket..to_ms)
+            .step_by(size_ms as usize)
+            .filter_map(|bucket| {
+                let (clicks, unique_clicks) = buckets.get(&bucket).copied().unwrap_or_default();
+
+                DateTime::from_timestamp_millis(bucket).map(|starts_at| AnalyticsBucket {
+                    starts_at,
+                    clicks,
+                    unique_clicks,
+                })
+            })
+            .collect();
+
+        let user_agent_rows = sqlx::query!(
+            r#"
+                select req_browser, req_user_agent, count(*) as "clicks!: i64"
+                from url_analytics
+                where url_id = ? and created_at >= ? and created_at < ?
+                group by req_browser, req_user_agent
+            "#,
+            url_id,
+            from_ms,
+            to_ms,
+        )
+        .fetch_all(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let mut user_agents = HashMap::new();
+
+        for row in user_agent_rows {
+            let family = row.req_browser.unwrap_or_else(|| {
+                browser(row.req_user_agent.as_deref().unwrap_or_default()).to_string()
+            });
+            *user_agents.entry(family).or_default() += row.clicks;
+        }
+
+        let referrer_rows = sqlx::query!(
+            r#"
+                select req_referer, count(*) as "clicks!: i64"
+                from url_analytics
+                where url_id = ? and created_at >= ? and created_at < ?
+                group by req_referer
+            "#,
+            url_id,
+            from_ms,
+            to_ms,
+        )
+        .fetch_all(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let mut referrers = HashMap::new();
+
+        for row in referrer_rows {
+            *referrers.entry(referrer_host(row.req_referer)).or_default() += row.clicks;
+        }
+
+        Ok(LinkAnalytics {
+            from,
+            to,
+            interval,
+            total_clicks: totals.clicks + rollup_totals.clicks,
+            unique_clicks: totals.unique_clicks + rollup_totals.unique_clicks,
+            series,
+            user_agents: breakdown(user_agents),
+            referrers: breakdown(referrers),
+        })
+    }
+
+    async fn get_analytics_salt(&self, day: i64) -> Result<Vec<u8>, InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        let salt = sqlx::query!(
+            r#"
+                insert into analytics_salt (day, salt, created_at, updated_at)
+                values (?, randomblob(32), ?, ?)
+                on conflict (day) do update set day = excluded.day
+                returning salt
+            "#,
+            day,
+            now_ms,
+            now_ms,
+        )
+        .fetch_one(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?
+        .salt;
+
+        Ok(salt)
+    }
+
+    // Cutoffs are counted back from midnight so a day is never split between the rollup and the raw
+    // clicks, which would double count its unique clicks.
+    async fn apply_retention(&self, defaults: &PrivacySettings) -> Result<(), InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+        let today = now_ms.div_euclid(DAY_MS) * DAY_MS;
+        let rollup = RetentionAction::Rollup;
+
+        let mut tx = self.conn().begin().await.map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                insert into url_analytics_rollup (url_id, day, clicks, unique_clicks, created_at, updated_at)
+                select
+                    url_analytics.url_id,
+                    (url_analytics.created_at / 86400000) * 86400000,
+                    count(*),
+                    count(distinct url_analytics.req_client_ip),
+                    ?,
+                    ?
+                from url_analytics
+                join url on url.id = url_analytics.url_id
+                left join organisation on organisation.id = url.organisation_id
+                where url_analytics.created_at < ? - 86400000 * coalesce(organisation.analytics_retention_days, ?)
+                and coalesce(organisation.analytics_retention_action, ?) = ?
+                group by 1, 2
+                on conflict (url_id, day) do update set
+                    clicks = clicks + excluded.clicks,
+                    unique_clicks = unique_clicks + excluded.unique_clicks,
+                    updated_at = excluded.updated_at
+            "#,
+            now_ms,
+            now_ms,
+            today,
+            defaults.retention_days,
+            defaults.retention_action,
+            rollup,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                with expired as (
+                    select url_analytics.url_id, count(*) as clicks
+                    from url_analytics
+                    join url on url.id = url_analytics.url_id
+                    left join organisation on organisation.id = url.organisation_id
+                    where url_analytics.created_at < ? - 86400000 * coalesce(organisation.analytics_retention_days, ?)
+                    group by url_analytics.url_id
+                )
+                update url set purged_clicks = purged_clicks + expired.clicks
+                from expired
+                where url.id = expired.url_id
+            "#,
+            today,
+            defaults.retention_days,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                delete from url_analytics
+                where created_at < ? - 86400000 * coalesce(
+                    (
+                        select organisation.analytics_retention_days from url
+                        join organisation on organisation.id = url.organisation_id
+                        where url.id = url_analytics.url_id
+                    ),
+                    ?
+                )
+            "#,
+            today,
+            defaults.retention_days,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                delete from url_report
+                where resolved_at is not null
+                and created_at < ? - 86400000 * coalesce(
+                    (
+                        select organisation.analytics_retention_days from url
+                        join organisation on organisation.id = url.organisation_id
+                        where url.id = url_report.url_id
+                    ),
+                    ?
+                )
+            "#,
+            today,
+            defaults.retention_days,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                update url_report set req_client_ip = null, updated_at = ?
+                where req_client_ip is not null
+                and created_at < ? - 86400000 * coalesce(
+                    (
+                        select organisation.analytics_retention_days from url
+                        join organisation on organisation.id = url.organisation_id
+                        where url.id = url_report.url_id
+                    ),
+                    ?
+                )
+            "#,
+            now_ms,
+            today,
+            defaults.retention_days,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                delete from analytics_salt where day < ?
+            "#,
+            today,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        tx.commit().await.map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::util_app_state::TestDatabase, sqlx::SqlitePool};
+
+    async fn create_link(conn: &SqlitePool, key: &str) -> Uuid {
+        let id = Uuid::now_v7();
+
+        sqlx::query(
+            "insert into url (id, key, url, created_at, updated_at) values (?, ?, ?, 0, 0)",
+        )
+        .bind(id)
+        .bind(key)
+        .bind("https://example.com/")
+        .execute(conn)
+        .await
+        .unwrap();
+
+        id
+    }
+
+    async fn count_clicks(conn: &SqlitePool, url_id: Uuid) -> i64 {
+        sqlx::query_scalar("select count(*) from url_analytics where url_id = ?")
+            .bind(url_id)
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    }
+
+    fn click(url_id: Uuid, url_variant_id: Option<Uuid>) -> ClickEvent {
+        ClickEvent {
+            id: Uuid::now_v7(),
+            url_id,
+            url_variant_id,
+            req_client_ip: "192.0.2.1".to_string(),
+            req_user_agent: None,
+            req_referer: None,
+            req_accept_language: None,
+            req_host: "itty.pro".to_string(),
+            req_query: None,
+            req_device: "desktop",
+            req_browser: "other",
+            req_os: "other",
+            req_country: None,
+            created_at: 0,
+        }
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn record_clicks_skips_links_deleted_while_queued(conn: SqlitePool) {
+        let kept = create_link(&conn, "kept").await;
+        let deleted = create_link(&conn, "deleted").await;
+
+        let clicks = vec![click(kept, None), click(deleted, None), click(kept, None)];
+
+        sqlx::query("delete from url where id = ?")
+            .bind(deleted)
+            .execute(&conn)
+            .await
+            .unwrap();
+
+        let state = TestDatabase(conn);
+
+        state.record_clicks(&clicks).await.unwrap();
+
+        assert_eq!(count_clicks(&state.0, kept).await, 2);
+        assert_eq!(count_clicks(&state.0, deleted).await, 0);
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn record_clicks_clears_replaced_variants(conn: SqlitePool) {
+        let url_id = create_link(&conn, "variants").await;
+
+        let state = TestDatabase(conn);
+
+        state
+            .record_clicks(&[click(url_id, Some(Uuid::now_v7()))])
+            .await
+            .unwrap();
+
+        let url_variant_id: Option<Uuid> =
+            sqlx::query_scalar("select url_variant_id from url_analytics where url_id = ?")
+                .bind(url_id)
+                .fetch_one(&state.0)
+                .await
+                .unwrap();
+
+        assert_eq!(url_variant_id, None);
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn apply_retention_rolls_up_whole_days(conn: SqlitePool) {
+        let url_id = create_link(&conn, "retention").await;
+        let today = Utc::now().timestamp_millis().div_euclid(DAY_MS) * DAY_MS;
+        let cutoff = today - DAY_MS * 30;
+
+        let state = TestDatabase(conn);
+
+        state
+            .record_clicks(&[
+                ClickEvent {
+                    created_at: cutoff - 1,
+                    ..click(url_id, None)
+                },
+                ClickEvent {
+                    created_at: cutoff,
+                    ..click(url_id, None)
+                },
+            ])
+            .await
+            .unwrap();
+
+        state
+            .apply_retention(&PrivacySettings {
+                ip_mode: None,
+                retention_days: Some(30),
+                retention_action: Some(RetentionAction::Rollup),
+            })
+            .await
+            .unwrap();
+
+        let rollup: Vec<(i64, i64)> =
+            sqlx::query_as("select day, clicks from url_analytics_rollup where url_id = ?")
+                .bind(url_id)
+                .fetch_all(&state.0)
+                .await
+                .unwrap();
+
+        assert_eq!(count_clicks(&state.0, url_id).await, 1);
+        assert_eq!(rollup, [(cutoff - DAY_MS, 1)]);
+    }
+}
@@ -0,0 +1,927 @@
+use {
+    crate::{
+        util_app_error::InternalServerError, util_app_state::Database, util_uuid::uuid_and_ts,
+    },
+    axum::{response::IntoResponse, Json},
+    chrono::{DateTime, Utc},
+    hyper::StatusCode,
+    password_auth::generate_hash,
+    serde::{Deserialize, Deserializer, Serialize},
+    sqlx::types::Json as SqlJson,
+    std::collections::{BTreeSet, HashMap},
+    thiserror::Error,
+    tokio::task,
+    url::Url,
+    utoipa::{IntoParams, ToSchema},
+    uuid::Uuid,
+    veil::Redact,
+};
+
+const MAX_VARIANT_WEIGHT: i64 = 1_000_000;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Link {
+    #[serde(skip_serializing)]
+    pub id: Uuid,
+
+    pub key: String,
+    pub url: String,
+
+    #[serde(skip_serializing)]
+    pub user_id: Option<Uuid>,
+    pub organisation_id: Option<Uuid>,
+
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_clicks: Option<i64>,
+    pub fallback_url: Option<String>,
+    pub password_protected: bool,
+    pub redirect_status: i64,
+    pub forward_query: bool,
+    pub forward_path: bool,
+    pub ios_url: Option<String>,
+    pub android_url: Option<String>,
+    pub desktop_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image: Option<String>,
+    #[schema(value_type = HashMap<String, String>)]
+    pub country_urls: SqlJson<HashMap<String, String>>,
+    #[schema(value_type = Vec<LinkVariant>)]
+    pub variants: SqlJson<Vec<LinkVariant>>,
+    pub active_from: Option<DateTime<Utc>>,
+    pub active_until: Option<DateTime<Utc>>,
+    #[schema(value_type = Vec<LinkScheduleEntry>)]
+    pub schedule: SqlJson<Vec<LinkScheduleEntry>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub disabled_reason: Option<String>,
+    pub notes: Option<String>,
+    pub folder: Option<String>,
+    #[schema(value_type = Vec<String>)]
+    pub tags: SqlJson<Vec<String>>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct LinkVariant {
+    pub id: Uuid,
+    pub url: String,
+    pub weight: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewLinkVariant {
+    pub url: Url,
+    #[schema(minimum = 1, maximum = 1000000)]
+    pub weight: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct LinkScheduleEntry {
+    pub starts_at: DateTime<Utc>,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewLinkScheduleEntry {
+    pub starts_at: DateTime<Utc>,
+    pub url: Url,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkPage {
+    pub links: Vec<Link>,
+    pub next_cursor: Option<Uuid>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct LinkFilter {
+    pub tag: Option<String>,
+    pub folder: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct NewLink {
+    pub key: String,
+    pub url: String,
+    pub user_id: Uuid,
+    pub organisation_id: Option<Uuid>,
+}
+
+#[derive(Redact, Deserialize, ToSchema)]
+pub struct LinkUpdate {
+    pub key: Option<String>,
+    pub url: Option<Url>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<i64>, minimum = 1)]
+    pub max_clicks: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<Url>)]
+    pub fallback_url: Option<Option<Url>>,
+    #[redact]
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub password: Option<Option<String>>,
+    #[schema(minimum = 301, maximum = 308)]
+    pub redirect_status: Option<i64>,
+    pub forward_query: Option<bool>,
+    pub forward_path: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<Url>)]
+    pub ios_url: Option<Option<Url>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<Url>)]
+    pub android_url: Option<Option<Url>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<Url>)]
+    pub desktop_url: Option<Option<Url>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub og_title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub og_description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<Url>)]
+    pub og_image: Option<Option<Url>>,
+    #[schema(value_type = Option<HashMap<String, Url>>)]
+    pub country_urls: Option<HashMap<String, Url>>,
+    pub variants: Option<Vec<NewLinkVariant>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub active_from: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub active_until: Option<Option<DateTime<Utc>>>,
+    pub schedule: Option<Vec<NewLinkScheduleEntry>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub notes: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub folder: Option<Option<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl LinkUpdate {
+    pub fn destinations(&self) -> Vec<&Url> {
+        let mut destinations: Vec<&Url> = [
+            self.url.as_ref(),
+            self.fallback_url.as_ref().and_then(Option::as_ref),
+            self.ios_url.as_ref().and_then(Option::as_ref),
+            self.android_url.as_ref().and_then(Option::as_ref),
+            self.desktop_url.as_ref().and_then(Option::as_ref),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        destinations.extend(self.country_urls.iter().flat_map(HashMap::values));
+        destinations.extend(self.variants.iter().flatten().map(|variant| &variant.url));
+        destinations.extend(self.schedule.iter().flatten().map(|entry| &entry.url));
+
+        destinations
+    }
+}
+
+fn update_url(update: Option<Option<Url>>, current: Option<String>) -> Option<String> {
+    update.map_or(current, |url| url.map(|url| url.to_string()))
+}
+
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Error, Debug, Serialize, ToSchema)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum LinkError {
+    #[error("link not found")]
+    NotFound,
+    #[error("key taken")]
+    KeyTaken,
+    #[error("key must be at least {min_length} characters")]
+    KeyTooShort { min_length: usize },
+    #[error("key must be at most {max_length} characters")]
+    KeyTooLong { max_length: usize },
+    #[error("key may only contain {allowed_characters}")]
+    KeyInvalidCharacters { allowed_characters: String },
+    #[error("key is reserved")]
+    KeyReserved { key: String },
+    #[error("url is invalid")]
+    InvalidUrl { reason: String },
+    #[error("url scheme is not allowed")]
+    UrlSchemeNotAllowed { scheme: String },
+    #[error("url must have a host")]
+    UrlMissingHost,
+    #[error("url points back to this shortener")]
+    UrlRedirectLoop,
+    #[error("url domain is blocked")]
+    UrlDomainBlocked { domain: String },
+    #[error("url domain is not allowed")]
+    UrlDomainNotAllowed { domain: String },
+    #[error("url is on a threat list")]
+    UrlThreat { threat_type: String },
+    #[error("redirect status must be one of 301, 302, 307 or 308")]
+    InvalidRedirectStatus,
+    #[error("max clicks must be at least 1")]
+    InvalidMaxClicks,
+    #[error("country must be an ISO 3166-1 alpha-2 code")]
+    InvalidCountry { country: String },
+    #[error("variant weight must be between 1 and {}", MAX_VARIANT_WEIGHT)]
+    InvalidVariantWeight,
+    #[error("active from must be before active until")]
+    InvalidActiveWindow,
+    #[error("tag must not be empty")]
+    InvalidTag,
+    #[error("folder must not be empty")]
+    InvalidFolder,
+    #[error(transparent)]
+    InternalServerError {
+        #[serde(skip)]
+        #[from]
+        error: anyhow::Error,
+    },
+}
+
+impl From<InternalServerError> for LinkError {
+    fn from(value: InternalServerError) -> Self {
+        Self::InternalServerError { error: value.0 }
+    }
+}
+
+impl Into<StatusCode> for &LinkError {
+    fn into(self) -> StatusCode {
+        match self {
+            LinkError::NotFound => StatusCode::NOT_FOUND,
+            LinkError::KeyTaken => StatusCode::CONFLICT,
+            LinkError::KeyTooShort { min_length: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::KeyTooLong { max_length: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::KeyInvalidCharacters {
+                allowed_characters: _,
+            } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::KeyReserved { key: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidUrl { reason: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::UrlSchemeNotAllowed { scheme: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::UrlMissingHost => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::UrlRedirectLoop => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::UrlDomainBlocked { domain: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::UrlDomainNotAllowed { domain: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::UrlThreat { threat_type: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidRedirectStatus => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidMaxClicks => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidCountry { country: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidVariantWeight => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidActiveWindow => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidTag => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InvalidFolder => StatusCode::UNPROCESSABLE_ENTITY,
+            LinkError::InternalServerError { error: _ } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for LinkError {
+    fn into_response(self) -> axum::response::Response {
+        (Into::<StatusCode>::into(&self), Json(self)).into_response()
+    }
+}
+
+#[async_trait::async_trait]
+pub trait LinkStoreExt {
+    async fn list_links(
+        &self,
+        user_id: &Uuid,
+        filter: &LinkFilter,
+        cursor: Option<Uuid>,
+        limit: i64,
+    ) -> Result<LinkPage, InternalServerError>;
+    async fn get_link(
+        &self,
+        user_id: &Uuid,
+        key: &str,
+    ) -> Result<Option<Link>, InternalServerError>;
+    async fn update_link(
+        &self,
+        user_id: &Uuid,
+        key: &str,
+        update: LinkUpdate,
+    ) -> Result<Link, LinkError>;
+    async fn try_create_link(&self, link: &NewLink) -> Result<bool, InternalServerError>;
+    async fn disable_link(
+        &self,
+        key: &str,
+        disabled_reason: Option<String>,
+    ) -> Result<bool, InternalServerError>;
+    async fn enable_link(&self, key: &str) -> Result<bool, InternalServerError>;
+    async fn delete_link(&self, user_id: &Uuid, key: &str) -> Result<bool, InternalServerError>;
+}
+
+#[async_trait::async_trait]
+impl<AppState: Database> LinkStoreExt for AppState {
+    async fn list_links(
+        &self,
+        user_id: &Uuid,
+        filter: &LinkFilter,
+        cursor: Option<Uuid>,
+        limit: i64,
+    ) -> Result<LinkPage, InternalServerError> {
+        let limit_plus_one = limit + 1;
+
+        let mut links = sqlx::query_as!(
+            Link,
+            r#"
+                select
+                    id as "id: Uuid",
+                    key,
+                    url,
+                    user_id as "user_id: Uuid",
+                    organisation_id as "organisation_id: Uuid",
+                    expires_at / 1000 as "expires_at?: DateTime<Utc>",
+                    max_clicks,
+                    fallback_url,
+                    password is not null as "password_protected!: bool",
+                    redirect_status,
+                    forward_query as "forward_query: bool",
+                    forward_path as "forward_path: bool",
+                    ios_url,
+                    android_url,
+                    desktop_url,
+                    og_title,
+                    og_description,
+                    og_image,
+                    (
+                        select json_group_object(url_country.country, url_country.url)
+                        from url_country
+                        where url_country.url_id = url.id
+                    ) as "country_urls!: SqlJson<HashMap<String, String>>",
+                    (
+                        select json_group_array(json_object(
+                            'id', lower(hex(url_variant.id)),
+                            'url', url_variant.url,
+                            'weight', url_variant.weight
+                        ))
+                        from url_variant
+                        where url_variant.url_id = url.id
+                    ) as "variants!: SqlJson<Vec<LinkVariant>>",
+                    active_from / 1000 as "active_from?: DateTime<Utc>",
+                    active_until / 1000 as "active_until?: DateTime<Utc>",
+                    (
+                        select json_group_array(json_object(
+                            'starts_at', strftime('%Y-%m-%dT%H:%M:%fZ', url_schedule.starts_at / 1000.0, 'unixepoch'),
+                            'url', url_schedule.url
+                        ) order by url_schedule.starts_at)
+                        from url_schedule
+                        where url_schedule.url_id = url.id
+                    ) as "schedule!: SqlJson<Vec<LinkScheduleEntry>>",
+                    disabled_at / 1000 as "disabled_at?: DateTime<Utc>",
+                    disabled_reason,
+                    notes,
+                    folder,
+                    (
+                        select json_group_array(url_tag.tag order by url_tag.tag)
+                        from url_tag
+                        where url_tag.url_id = url.id
+                    ) as "tags!: SqlJson<Vec<String>>",
+                    created_at / 1000 as "created_at!: DateTime<Utc>",
+                    updated_at / 1000 as "updated_at!: DateTime<Utc>"
+                from url
+                where (
+                    user_id = ?
+                    or organisation_id in (
+                        select organisation_id from organisation_access
+                        where user_id = ?
+                    )
+                ) and (? is null or folder = ?)
+                and (? is null or exists (
+                    select 1 from url_tag
+                    where url_tag.url_id = url.id and url_tag.tag = ?
+                ))
+                and (? is null or instr(lower(notes), lower(?)) > 0)
+                and (? is null or id < ?)
+                order by id desc
+                limit ?
+            "#,
+            user_id,
+            user_id,
+            filter.folder,
+            filter.folder,
+            filter.tag,
+            filter.tag,
+            filter.notes,
+            filter.notes,
+            cursor,
+            cursor,
+            limit_plus_one,
+        )
+        .fetch_all(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let next_cursor = if links.len() as i64 > limit {
+            links.truncate(limit as usize);
+            links.last().map(|link| link.id)
+        } else {
+            None
+        };
+
+        Ok(LinkPage { links, next_cursor })
+    }
+
+    async fn get_link(
+        &self,
+        user_id: &Uuid,
+        key: &str,
+    ) -> Result<Option<Link>, InternalServerError> {
+        let link = sqlx::query_as!(
+            Link,
+            r#"
+                select
+                    id as "id: Uuid",
+                    key,
+                    url,
+                    user_id as "user_id: Uuid",
+                    organisation_id as "organisation_id: Uuid",
+                    expires_at / 1000 as "expires_at?: DateTime<Utc>",
+                    max_clicks,
+                    fallback_url,
+                    password is not null as "password_protected!: bool",
+                    redirect_status,
+                    forward_query as "forward_query: bool",
+                    forward_path as "forward_path: bool",
+                    ios_url,
+                    android_url,
+                    desktop_url,
+                    og_title,
+                    og_description,
+                    og_image,
+                    (
+                        select json_group_object(url_country.country, url_country.url)
+                        from url_country
+                        where url_country.url_id = url.id
+                    ) as "country_urls!: SqlJson<HashMap<String, String>>",
+                    (
+                        select json_group_array(json_object(
+                            'id', lower(hex(url_variant.id)),
+                            'url', url_variant.url,
+                            'weight', url_variant.weight
+                        ))
+                        from url_variant
+                        where url_variant.url_id = url.id
+                    ) as "variants!: SqlJson<Vec<LinkVariant>>",
+                    active_from / 1000 as "active_from?: DateTime<Utc>",
+                    active_until / 1000 as "active_until?: DateTime<Utc>",
+                    (
+                        select json_group_array(json_object(
+                            'starts_at', strftime('%Y-%m-%dT%H:%M:%fZ', url_schedule.starts_at / 1000.0, 'unixepoch'),
+                            'url', url_schedule.url
+                        ) order by url_schedule.starts_at)
+                        from url_schedule
+                        where url_schedule.url_id = url.id
+                    ) as "schedule!: SqlJson<Vec<LinkScheduleEntry>>",
+                    disabled_at / 1000 as "disabled_at?: DateTime<Utc>",
+                    disabled_reason,
+                    notes,
+                    folder,
+                    (
+                        select json_group_array(url_tag.tag order by url_tag.tag)
+                        from url_tag
+                        where url_tag.url_id = url.id
+                    ) as "tags!: SqlJson<Vec<String>>",
+                    created_at / 1000 as "created_at!: DateTime<Utc>",
+                    updated_at / 1000 as "updated_at!: DateTime<Utc>"
+                from url
+                where key = ? and (
+                    user_id = ?
+                    or organisation_id in (
+                        select organisation_id from organisation_access
+                        where user_id = ?
+                    )
+                )
+            "#,
+            key,
+            user_id,
+            user_id,
+        )
+        .fetch_optional(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(link)
+    }
+
+    async fn update_link(
+        &self,
+        user_id: &Uuid,
+        key: &str,
+        update: LinkUpdate,
+    ) -> Result<Link, LinkError> {
+        let link = self
+            .get_link(user_id, key)
+            .await?
+            .ok_or(LinkError::NotFound)?;
+
+        let now_ms = Utc::now().timestamp_millis();
+        let new_key = update.key.unwrap_or(link.key);
+        let new_url = update.url.map_or(link.url, |url| url.to_string());
+        let expires_at_changed = update.expires_at.is_some();
+        let new_expires_at = update
+            .expires_at
+            .flatten()
+            .map(|expires_at| expires_at.timestamp_millis());
+        let new_max_clicks = match update.max_clicks {
+            Some(Some(max_clicks)) if max_clicks < 1 => return Err(LinkError::InvalidMaxClicks),
+            Some(max_clicks) => max_clicks,
+            None => link.max_clicks,
+        };
+        let active_from_changed = update.active_from.is_some();
+        let active_until_changed = update.active_until.is_some();
+        let new_active_from = update.active_from.unwrap_or(link.active_from);
+        let new_active_until = update.active_until.unwrap_or(link.active_until);
+        if let (Some(active_from), Some(active_until)) = (new_active_from, new_active_until) {
+            if active_from >= active_until {
+                return Err(LinkError::InvalidActiveWindow);
+            }
+        }
+        let new_active_from = new_active_from.map(|active_from| active_from.timestamp_millis());
+        let new_active_until = new_active_until.map(|active_until| active_until.timestamp_millis());
+        let new_fallback_url = update_url(update.fallback_url, link.fallback_url);
+        let new_ios_url = update_url(update.ios_url, link.ios_url);
+        let new_android_url = update_url(update.android_url, link.android_url);
+        let new_desktop_url = update_url(update.desktop_url, link.desktop_url);
+        let new_og_title = update.og_title.unwrap_or(link.og_title);
+        let new_og_description = update.og_description.unwrap_or(link.og_description);
+        let new_og_image = update_url(update.og_image, link.og_image);
+        let new_redirect_status = match update.redirect_status {
+            Some(redirect_status @ (301 | 302 | 307 | 308)) => redirect_status,
+            Some(_) => return Err(LinkError::InvalidRedirectStatus),
+            None => link.redirect_status,
+        };
+        let new_forward_query = update.forward_query.unwrap_or(link.forward_query);
+        let new_forward_path = update.forward_path.unwrap_or(link.forward_path);
+        let new_notes = update.notes.unwrap_or(link.notes);
+        let new_folder = match update.folder {
+            Some(Some(folder)) if folder.trim().is_empty() => return Err(LinkError::InvalidFolder),
+            Some(folder) => folder.map(|folder| folder.trim().to_string()),
+            None => link.folder,
+        };
+        let new_tags = match update.tags {
+            Some(tags) => Some(
+                tags.into_iter()
+                    .map(|tag| match tag.trim() {
+                        "" => Err(LinkError::InvalidTag),
+                        tag => Ok(tag.to_string()),
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()?,
+            ),
+            None => None,
+        };
+        let password_changed = update.password.is_some();
+        let new_password = match update.password.flatten() {
+            Some(password) => Some(
+                task::spawn_blocking(move || generate_hash(password))
+                    .await
+                    .map_err(anyhow::Error::new)?,
+            ),
+            None => None,
+        };
+        let new_country_urls = match update.country_urls {
+            Some(country_urls) => Some(
+                country_urls
+                    .into_iter()
+                    .map(|(country, url)| {
+                        if country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()) {
+                            Ok((country.to_ascii_uppercase(), url.to_string()))
+                        } else {
+                            Err(LinkError::InvalidCountry { country })
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            None => None,
+        };
+        if let Some(variants) = &update.variants {
+            if variants
+                .iter()
+                .any(|variant| !(1..=MAX_VARIANT_WEIGHT).contains(&variant.weight))
+            {
+                return Err(LinkError::InvalidVariantWeight);
+            }
+        }
+
+        let mut tx = self.conn().begin().await.map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                update url set
+                    key = ?,
+                    url = ?,
+                    expires_at = case when ? then ? else expires_at end,
+                    max_clicks = ?,
+                    fallback_url = ?,
+                    password = case when ? then ? else password end,
+                    redirect_status = ?,
+                    forward_query = ?,
+                    forward_path = ?,
+                    ios_url = ?,
+                    android_url = ?,
+                    desktop_url = ?,
+                    og_title = ?,
+                    og_description = ?,
+                    og_image = ?,
+                    active_from = case when ? then ? else active_from end,
+                    active_until = case when ? then ? else active_until end,
+                    notes = ?,
+                    folder = ?,
+                    updated_at = ?
+                where id = ?
+            "#,
+            new_key,
+            new_url,
+            expires_at_changed,
+            new_expires_at,
+            new_max_clicks,
+            new_fallback_url,
+            password_changed,
+            new_password,
+            new_redirect_status,
+            new_forward_query,
+            new_forward_path,
+            new_ios_url,
+            new_android_url,
+            new_desktop_url,
+            new_og_title,
+            new_og_description,
+            new_og_image,
+            active_from_changed,
+            new_active_from,
+            active_until_changed,
+            new_active_until,
+            new_notes,
+            new_folder,
+            now_ms,
+            link.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(err) if err.is_unique_violation() => LinkError::KeyTaken,
+            _ => LinkError::InternalServerError { error: err.into() },
+        })?;
+
+        if let Some(country_urls) = new_country_urls {
+            sqlx::query!(
+                r#"
+                    delete from url_country where url_id = ?
+                "#,
+                link.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+
+            for (country, url) in country_urls {
+                let (id, now_ts) = uuid_and_ts();
+                let now_ms = now_ts.timestamp_millis();
+
+                sqlx::query!(
+                    r#"
+                        insert into url_country (id, url_id, country, url, created_at, updated_at) values (?, ?, ?, ?, ?, ?)
+                    "#,
+                    id,
+                    link.id,
+                    country,
+                    url,
+                    now_ms,
+                    now_ms,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+        }
+
+        if let Some(variants) = update.variants {
+            let urls: Vec<String> = variants
+                .iter()
+                .map(|variant| variant.url.to_string())
+                .collect();
+
+            let existing = sqlx::query!(
+                r#"
+                    select id as "id: Uuid", url from url_variant where url_id = ?
+                "#,
+                link.id,
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+
+            for row in existing.iter().filter(|row| !urls.contains(&row.url)) {
+                sqlx::query!(
+                    r#"
+                        delete from url_variant where id = ?
+                    "#,
+                    row.id,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+
+            for (url, variant) in urls.into_iter().zip(variants) {
+                let (id, now_ts) = uuid_and_ts();
+                let now_ms = now_ts.timestamp_millis();
+
+                sqlx::query!(
+                    r#"
+                        insert into url_variant (id, url_id, url, weight, created_at, updated_at) values (?, ?, ?, ?, ?, ?)
+                        on conflict(url_id, url) do update set
+                            weight = excluded.weight,
+                            updated_at = excluded.updated_at
+                    "#,
+                    id,
+                    link.id,
+                    url,
+                    variant.weight,
+                    now_ms,
+                    now_ms,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+        }
+
+        if let Some(schedule) = update.schedule {
+            sqlx::query!(
+                r#"
+                    delete from url_schedule where url_id = ?
+                "#,
+                link.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+
+            for entry in schedule {
+                let (id, now_ts) = uuid_and_ts();
+                let now_ms = now_ts.timestamp_millis();
+                let starts_at = entry.starts_at.timestamp_millis();
+                let url = entry.url.to_string();
+
+                sqlx::query!(
+                    r#"
+                        insert into url_schedule (id, url_id, starts_at, url, created_at, updated_at) values (?, ?, ?, ?, ?, ?)
+                        on conflict(url_id, starts_at) do update set
+                            url = excluded.url,
+                            updated_at = excluded.updated_at
+                    "#,
+                    id,
+                    link.id,
+                    starts_at,
+                    url,
+                    now_ms,
+                    now_ms,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+        }
+
+        if let Some(tags) = new_tags {
+            sqlx::query!(
+                r#"
+                    delete from url_tag where url_id = ?
+                "#,
+                link.id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+
+            for tag in tags {
+                let (id, now_ts) = uuid_and_ts();
+                let now_ms = now_ts.timestamp_millis();
+
+                sqlx::query!(
+                    r#"
+                        insert into url_tag (id, url_id, tag, created_at, updated_at) values (?, ?, ?, ?, ?)
+                    "#,
+                    id,
+                    link.id,
+                    tag,
+                    now_ms,
+                    now_ms,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+        }
+
+        tx.commit().await.map_err(anyhow::Error::new)?;
+
+        self.get_link(user_id, &new_key)
+            .await?
+            .ok_or(LinkError::NotFound)
+    }
+
+    async fn try_create_link(&self, link: &NewLink) -> Result<bool, InternalServerError> {
+        let (id, now_ts) = uuid_and_ts();
+        let now_ms = now_ts.timestamp_millis();
+
+        match sqlx::query!(
+            r#"
+                insert or abort into url (id, key, url, user_id, organisation_id, created_at, updated_at) values (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            link.key,
+            link.url,
+            link.user_id,
+            link.organisation_id,
+            now_ms,
+            now_ms,
+        )
+        .execute(self.conn())
+        .await
+        {
+            Ok(_) => Ok(true),
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(false),
+            Err(err) => Err(anyhow::Error::new(err).into()),
+        }
+    }
+
+    async fn disable_link(
+        &self,
+        key: &str,
+        disabled_reason: Option<String>,
+    ) -> Result<bool, InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        let result = sqlx::query!(
+            r#"
+                update url set disabled_at = ?, disabled_reason = ?, updated_at = ?
+                where key = ?
+            "#,
+            now_ms,
+            disabled_reason,
+            now_ms,
+            key,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn enable_link(&self, key: &str) -> Result<bool, InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        let result = sqlx::query!(
+            r#"
+                update url set disabled_at = null, disabled_reason = null, updated_at = ?
+                where key = ?
+            "#,
+            now_ms,
+            key,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_link(&self, user_id: &Uuid, key: &str) -> Result<bool, InternalServerError> {
+        let result = sqlx::query!(
+            r#"
+                delete from url
+                where key = ? and (
+                    user_id = ?
+                    or organisation_id in (
+                        select organisation_id from organisation_access
+                        where user_id = ?
+                    )
+                )
+            "#,
+            key,
+            user_id,
+            user_id,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
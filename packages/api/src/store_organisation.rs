@@ -0,0 +1,186 @@
+use {
+    crate::{
+        util_app_error::InternalServerError,
+        util_app_state::Database,
+        util_privacy::{IpMode, PrivacySettings, RetentionAction},
+        util_utm::UtmParameters,
+    },
+    chrono::Utc,
+    uuid::Uuid,
+};
+
+#[async_trait::async_trait]
+pub trait OrganisationStoreExt {
+    async fn is_organisation_member(
+        &self,
+        organisation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, InternalServerError>;
+    async fn get_organisation_utm(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<UtmParameters, InternalServerError>;
+    async fn set_organisation_utm(
+        &self,
+        organisation_id: &Uuid,
+        utm: &UtmParameters,
+    ) -> Result<(), InternalServerError>;
+    async fn get_organisation_privacy(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<PrivacySettings, InternalServerError>;
+    async fn set_organisation_privacy(
+        &self,
+        organisation_id: &Uuid,
+        privacy: &PrivacySettings,
+    ) -> Result<(), InternalServerError>;
+    async fn get_link_ip_mode(&self, key: &str) -> Result<Option<IpMode>, InternalServerError>;
+}
+
+#[async_trait::async_trait]
+impl<AppState: Database> OrganisationStoreExt for AppState {
+    async fn is_organisation_member(
+        &self,
+        organisation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, InternalServerError> {
+        let is_member = sqlx::query!(
+            r#"
+                select 1 as "is_member!: bool" from organisation_access
+                where organisation_id = ? and user_id = ?
+                limit 1
+            "#,
+            organisation_id,
+            user_id,
+        )
+        .fetch_optional(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?
+        .is_some();
+
+        Ok(is_member)
+    }
+
+    async fn get_organisation_utm(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<UtmParameters, InternalServerError> {
+        let utm = sqlx::query_as!(
+            UtmParameters,
+            r#"
+                select utm_source, utm_medium, utm_campaign, utm_term, utm_content
+                from organisation
+                where id = ?
+            "#,
+            organisation_id,
+        )
+        .fetch_optional(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(utm.unwrap_or_default())
+    }
+
+    async fn set_organisation_utm(
+        &self,
+        organisation_id: &Uuid,
+        utm: &UtmParameters,
+    ) -> Result<(), InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        sqlx::query!(
+            r#"
+                update organisation set
+                    utm_source = ?,
+                    utm_medium = ?,
+                    utm_campaign = ?,
+                    utm_term = ?,
+                    utm_content = ?,
+                    updated_at = ?
+                where id = ?
+            "#,
+            utm.utm_source,
+            utm.utm_medium,
+            utm.utm_campaign,
+            utm.utm_term,
+            utm.utm_content,
+            now_ms,
+            organisation_id,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+
+    async fn get_organisation_privacy(
+        &self,
+        organisation_id: &Uuid,
+    ) -> Result<PrivacySettings, InternalServerError> {
+        let privacy = sqlx::query_as!(
+            PrivacySettings,
+            r#"
+                select
+                    analytics_ip_mode as "ip_mode: IpMode",
+                    analytics_retention_days as retention_days,
+                    analytics_retention_action as "retention_action: RetentionAction"
+                from organisation
+                where id = ?
+            "#,
+            organisation_id,
+        )
+        .fetch_optional(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(privacy.unwrap_or_default())
+    }
+
+    async fn get_link_ip_mode(&self, key: &str) -> Result<Option<IpMode>, InternalServerError> {
+        let ip_mode = sqlx::query!(
+            r#"
+                select organisation.analytics_ip_mode as "ip_mode?: IpMode"
+                from url
+                left join organisation on organisation.id = url.organisation_id
+                where url.key = ?
+            "#,
+            key,
+        )
+        .fetch_optional(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?
+        .and_then(|row| row.ip_mode);
+
+        Ok(ip_mode)
+    }
+
+    async fn set_organisation_privacy(
+        &self,
+        organisation_id: &Uuid,
+        privacy: &PrivacySettings,
+    ) -> Result<(), InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        sqlx::query!(
+            r#"
+                update organisation set
+                    analytics_ip_mode = ?,
+                    analytics_retention_days = ?,
+                    analytics_retention_action = ?,
+                    updated_at = ?
+                where id = ?
+            "#,
+            privacy.ip_mode,
+            privacy.retention_days,
+            privacy.retention_action,
+            now_ms,
+            organisation_id,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+}
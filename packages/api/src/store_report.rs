@@ -0,0 +1,325 @@
+use {
+    crate::{
+        util_app_error::InternalServerError, util_app_state::Database, util_uuid::uuid_and_ts,
+    },
+    axum::{response::IntoResponse, Json},
+    chrono::{DateTime, Utc},
+    hyper::StatusCode,
+    serde::{Deserialize, Serialize},
+    thiserror::Error,
+    utoipa::ToSchema,
+    uuid::Uuid,
+    veil::Redact,
+};
+
+const MAX_DETAILS_LENGTH: usize = 2000;
+const MAX_EMAIL_LENGTH: usize = 254;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ReportReason {
+    Phishing,
+    Malware,
+    Spam,
+    Illegal,
+    Other,
+}
+
+#[derive(Redact, Deserialize, ToSchema)]
+pub struct NewReport {
+    pub reason: ReportReason,
+    #[redact(partial)]
+    pub details: Option<String>,
+    #[redact(partial)]
+    pub email: Option<String>,
+}
+
+impl NewReport {
+    fn validate(&self) -> Result<(), ReportError> {
+        if self
+            .details
+            .as_ref()
+            .is_some_and(|details| details.chars().count() > MAX_DETAILS_LENGTH)
+        {
+            return Err(ReportError::DetailsTooLong {
+                max_length: MAX_DETAILS_LENGTH,
+            });
+        }
+
+        if let Some(email) = &self.email {
+            let valid = email.len() <= MAX_EMAIL_LENGTH
+                && !email.chars().any(char::is_whitespace)
+                && email.split_once('@').is_some_and(|(local, domain)| {
+                    !local.is_empty()
+                        && !domain.contains('@')
+                        && domain.contains('.')
+                        && !domain.starts_with('.')
+                        && !domain.ends_with('.')
+                });
+
+            if !valid {
+                return Err(ReportError::InvalidEmail);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug, Serialize, ToSchema)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum ReportError {
+    #[error("link not found")]
+    NotFound,
+    #[error("details must be at most {max_length} characters")]
+    DetailsTooLong { max_length: usize },
+    #[error("invalid email")]
+    InvalidEmail,
+    #[error(transparent)]
+    InternalServerError {
+        #[serde(skip)]
+        #[from]
+        error: anyhow::Error,
+    },
+}
+
+impl From<InternalServerError> for ReportError {
+    fn from(value: InternalServerError) -> Self {
+        Self::InternalServerError { error: value.0 }
+    }
+}
+
+impl Into<StatusCode> for &ReportError {
+    fn into(self) -> StatusCode {
+        match self {
+            ReportError::NotFound => StatusCode::NOT_FOUND,
+            ReportError::DetailsTooLong { max_length: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            ReportError::InvalidEmail => StatusCode::UNPROCESSABLE_ENTITY,
+            ReportError::InternalServerError { error: _ } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ReportError {
+    fn into_response(self) -> axum::response::Response {
+        (Into::<StatusCode>::into(&self), Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Report {
+    pub id: Uuid,
+    pub key: String,
+    pub url: String,
+    pub user_id: Option<Uuid>,
+    pub link_disabled: bool,
+
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub reporter_email: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportPage {
+    pub reports: Vec<Report>,
+    pub next_cursor: Option<Uuid>,
+}
+
+#[async_trait::async_trait]
+pub trait ReportStoreExt {
+    async fn new_report(
+        &self,
+        key: &str,
+        report: NewReport,
+        req_client_ip: &str,
+    ) -> Result<(), ReportError>;
+    async fn list_reports(
+        &self,
+        resolved: bool,
+        cursor: Option<Uuid>,
+        limit: i64,
+    ) -> Result<ReportPage, InternalServerError>;
+    async fn resolve_reports(&self, key: &str) -> Result<u64, InternalServerError>;
+    async fn set_report_resolved(
+        &self,
+        report_id: &Uuid,
+        resolved: bool,
+    ) -> Result<bool, InternalServerError>;
+}
+
+#[async_trait::async_trait]
+impl<AppState: Database> ReportStoreExt for AppState {
+    async fn new_report(
+        &self,
+        key: &str,
+        report: NewReport,
+        req_client_ip: &str,
+    ) -> Result<(), ReportError> {
+        report.validate()?;
+
+        let (id, now_ts) = uuid_and_ts();
+        let now_ms = now_ts.timestamp_millis();
+
+        let result = sqlx::query!(
+            r#"
+                insert into url_report (id, url_id, reason, details, reporter_email, req_client_ip, created_at, updated_at)
+                select ?, id, ?, ?, ?, ?, ?, ? from url where key = ?
+            "#,
+            id,
+            report.reason,
+            report.details,
+            report.email,
+            req_client_ip,
+            now_ms,
+            now_ms,
+            key,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        if result.rows_affected() == 0 {
+            return Err(ReportError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn list_reports(
+        &self,
+        resolved: bool,
+        cursor: Option<Uuid>,
+        limit: i64,
+    ) -> Result<ReportPage, InternalServerError> {
+        let limit_plus_one = limit + 1;
+
+        let mut reports = sqlx::query_as!(
+            Report,
+            r#"
+                select
+                    url_report.id as "id: Uuid",
+                    url.key,
+                    url.url,
+                    url.user_id as "user_id: Uuid",
+                    url.disabled_at is not null as "link_disabled!: bool",
+                    url_report.reason as "reason: ReportReason",
+                    url_report.details,
+                    url_report.reporter_email,
+                    url_report.resolved_at / 1000 as "resolved_at?: DateTime<Utc>",
+                    url_report.created_at / 1000 as "created_at!: DateTime<Utc>"
+                from url_report
+                join url on url.id = url_report.url_id
+                where (url_report.resolved_at is not null) = ?
+                and (? is null or url_report.id < ?)
+                order by url_report.id desc
+                limit ?
+            "#,
+            resolved,
+            cursor,
+            cursor,
+            limit_plus_one,
+        )
+        .fetch_all(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        let next_cursor = if reports.len() as i64 > limit {
+            reports.truncate(limit as usize);
+            reports.last().map(|report| report.id)
+        } else {
+            None
+        };
+
+        Ok(ReportPage {
+            reports,
+            next_cursor,
+        })
+    }
+
+    async fn resolve_reports(&self, key: &str) -> Result<u64, InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        let result = sqlx::query!(
+            r#"
+                update url_report set resolved_at = ?, updated_at = ?
+                where resolved_at is null
+                and url_id in (select id from url where key = ?)
+            "#,
+            now_ms,
+            now_ms,
+            key,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn set_report_resolved(
+        &self,
+        report_id: &Uuid,
+        resolved: bool,
+    ) -> Result<bool, InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+        let resolved_at = resolved.then_some(now_ms);
+
+        let result = sqlx::query!(
+            r#"
+                update url_report set resolved_at = ?, updated_at = ?
+                where id = ?
+            "#,
+            resolved_at,
+            now_ms,
+            report_id,
+        )
+        .execute(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(details: Option<&str>, email: Option<&str>) -> NewReport {
+        NewReport {
+            reason: ReportReason::Spam,
+            details: details.map(str::to_string),
+            email: email.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn validates_new_reports() {
+        assert!(report(None, None).validate().is_ok());
+        assert!(report(Some("spam"), Some("reporter@example.com"))
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            report(Some(&"a".repeat(MAX_DETAILS_LENGTH + 1)), None).validate(),
+            Err(ReportError::DetailsTooLong { .. })
+        ));
+
+        for email in [
+            "",
+            "reporter",
+            "@example.com",
+            "reporter@example",
+            "a b@example.com",
+            "a@b@example.com",
+        ] {
+            assert!(matches!(
+                report(None, Some(email)).validate(),
+                Err(ReportError::InvalidEmail)
+            ));
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use {
+    crate::{
+        util_app_error::InternalServerError,
+        util_app_state::Database,
+        util_threat_list::{
+            full_hashes, FullHashes, ListUpdateResponse, ResponseType, ThreatListUpdates,
+        },
+        util_uuid::uuid_and_ts,
+    },
+    anyhow::{anyhow, bail},
+    base64::{engine::general_purpose::STANDARD, Engine},
+    sha2::{Digest, Sha256},
+    std::collections::BTreeSet,
+    url::Url,
+    uuid::Uuid,
+};
+
+#[async_trait::async_trait]
+pub trait ThreatListStoreExt {
+    async fn find_threat(&self, url: &Url) -> Result<Option<String>, InternalServerError>;
+    async fn apply_threat_list_updates(
+        &self,
+        updates: ThreatListUpdates,
+    ) -> Result<(), InternalServerError>;
+    async fn apply_threat_full_hashes(
+        &self,
+        full_hashes: FullHashes,
+    ) -> Result<(), InternalServerError>;
+}
+
+fn decode_additions(response: &ListUpdateResponse) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut additions = Vec::new();
+
+    for set in &response.additions {
+        let raw_hashes = match &set.raw_hashes {
+            Some(raw_hashes) => raw_hashes,
+            None => bail!(
+                "unsupported compression type {:?}, only RAW is supported",
+                set.compression_type
+            ),
+        };
+
+        if !(4..=32).contains(&raw_hashes.prefix_size) {
+            bail!("invalid prefix size {}", raw_hashes.prefix_size);
+        }
+
+        let bytes = STANDARD.decode(&raw_hashes.raw_hashes)?;
+
+        if bytes.len() % raw_hashes.prefix_size != 0 {
+            bail!("raw hashes are not a multiple of the prefix size");
+        }
+
+        additions.extend(bytes.chunks(raw_hashes.prefix_size).map(<[u8]>::to_vec));
+    }
+
+    Ok(additions)
+}
+
+fn decode_removals(response: &ListUpdateResponse) -> anyhow::Result<Vec<usize>> {
+    let mut removals = Vec::new();
+
+    for set in &response.removals {
+        match &set.raw_indices {
+            Some(raw_indices) => removals.extend(&raw_indices.indices),
+            None => bail!(
+                "unsupported compression type {:?}, only RAW is supported",
+                set.compression_type
+            ),
+        }
+    }
+
+    Ok(removals)
+}
+
+#[async_trait::async_trait]
+impl<AppState: Database> ThreatListStoreExt for AppState {
+    // A prefix shorter than 32 bytes only counts once a full hash from the same list confirms it.
+    async fn find_threat(&self, url: &Url) -> Result<Option<String>, InternalServerError> {
+        let hashes = full_hashes(url)
+            .iter()
+            .map(|hash| hash.iter().map(|byte| format!("{byte:02x}")).collect())
+            .collect::<Vec<String>>();
+        let hashes = serde_json::to_string(&hashes).map_err(anyhow::Error::new)?;
+
+        let row = sqlx::query!(
+            r#"
+                select threat_list.threat_type
+                from json_each(?) as hashes
+                join threat_hash_prefix
+                    on threat_hash_prefix.prefix between unhex(substr(hashes.value, 1, 8)) and unhex(hashes.value)
+                    and threat_hash_prefix.prefix = substr(unhex(hashes.value), 1, length(threat_hash_prefix.prefix))
+                join threat_list on threat_list.id = threat_hash_prefix.threat_list_id
+                where length(threat_hash_prefix.prefix) = 32
+                    or exists (
+                        select 1 from threat_full_hash
+                        where threat_full_hash.threat_list_id = threat_list.id
+                            and threat_full_hash.hash = unhex(hashes.value)
+                    )
+                limit 1
+            "#,
+            hashes,
+        )
+        .fetch_optional(self.conn())
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        Ok(row.map(|row| row.threat_type))
+    }
+
+    async fn apply_threat_list_updates(
+        &self,
+        updates: ThreatListUpdates,
+    ) -> Result<(), InternalServerError> {
+        let mut tx = self.conn().begin().await.map_err(anyhow::Error::new)?;
+
+        for response in updates.list_update_responses {
+            let additions = decode_additions(&response)?;
+            let removals = decode_removals(&response)?;
+            let (id, now_ts) = uuid_and_ts();
+            let now_ms = now_ts.timestamp_millis();
+
+            let threat_list_id = sqlx::query!(
+                r#"
+                    insert into threat_list (id, threat_type, platform_type, threat_entry_type, client_state, created_at, updated_at)
+                    values (?, ?, ?, ?, ?, ?, ?)
+                    on conflict (threat_type, platform_type, threat_entry_type) do update set
+                        client_state = excluded.client_state,
+                        updated_at = excluded.updated_at
+                    returning id as "id: Uuid"
+                "#,
+                id,
+                response.threat_type,
+                response.platform_type,
+                response.threat_entry_type,
+                response.new_client_state,
+                now_ms,
+                now_ms,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?
+            .id;
+
+            let mut prefixes: BTreeSet<Vec<u8>> = match response.response_type {
+                ResponseType::FullUpdate => BTreeSet::new(),
+                _ => sqlx::query!(
+                    r#"
+                        select prefix from threat_hash_prefix where threat_list_id = ?
+                    "#,
+                    threat_list_id,
+                )
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?
+                .into_iter()
+                .map(|row| row.prefix)
+                .collect(),
+            };
+
+            let sorted: Vec<Vec<u8>> = prefixes.iter().cloned().collect();
+
+            for index in removals {
+                let prefix = sorted
+                    .get(index)
+                    .ok_or_else(|| anyhow!("removal index {index} is out of range"))?;
+
+                prefixes.remove(prefix);
+            }
+
+            prefixes.extend(additions);
+
+            if let Some(checksum) = &response.checksum {
+                let expected = STANDARD
+                    .decode(&checksum.sha256)
+                    .map_err(anyhow::Error::new)?;
+                let actual = prefixes
+                    .iter()
+                    .fold(Sha256::new(), |hasher, prefix| hasher.chain_update(prefix))
+                    .finalize();
+
+                if expected != actual.as_slice() {
+                    return Err(anyhow!(
+                        "checksum mismatch for {} {} {}",
+                        response.threat_type,
+                        response.platform_type,
+                        response.threat_entry_type
+                    )
+                    .into());
+                }
+            }
+
+            sqlx::query!(
+                r#"
+                    delete from threat_hash_prefix where threat_list_id = ?
+                "#,
+                threat_list_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+
+            for prefix in prefixes {
+                sqlx::query!(
+                    r#"
+                        insert into threat_hash_prefix (threat_list_id, prefix) values (?, ?)
+                    "#,
+                    threat_list_id,
+                    prefix,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+        }
+
+        tx.commit().await.map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+
+    async fn apply_threat_full_hashes(
+        &self,
+        full_hashes: FullHashes,
+    ) -> Result<(), InternalServerError> {
+        let mut tx = self.conn().begin().await.map_err(anyhow::Error::new)?;
+
+        sqlx::query!(
+            r#"
+                delete from threat_full_hash
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        for threat_match in full_hashes.matches {
+            let hash = STANDARD
+                .decode(&threat_match.threat.hash)
+                .map_err(anyhow::Error::new)?;
+
+            if hash.len() != 32 {
+                return Err(anyhow!("invalid full hash size {}", hash.len()).into());
+            }
+
+            sqlx::query!(
+                r#"
+                    insert into threat_full_hash (threat_list_id, hash)
+                    select id, ? from threat_list
+                    where threat_type = ? and platform_type = ? and threat_entry_type = ?
+                    on conflict do nothing
+                "#,
+                hash,
+                threat_match.threat_type,
+                threat_match.platform_type,
+                threat_match.threat_entry_type,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+        }
+
+        tx.commit().await.map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::util_app_state::TestDatabase, sqlx::SqlitePool};
+
+    const FIXTURE: &str = include_str!("../fixtures/threat_list_updates.json");
+    const FULL_HASHES: &str = include_str!("../fixtures/threat_full_hashes.json");
+
+    async fn fixture_state(conn: SqlitePool) -> TestDatabase {
+        let state = TestDatabase(conn);
+
+        state
+            .apply_threat_list_updates(serde_json::from_str(FIXTURE).unwrap())
+            .await
+            .unwrap();
+        state
+            .apply_threat_full_hashes(serde_json::from_str(FULL_HASHES).unwrap())
+            .await
+            .unwrap();
+
+        state
+    }
+
+    async fn find(state: &TestDatabase, url: &str) -> Option<String> {
+        state.find_threat(&Url::parse(url).unwrap()).await.unwrap()
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn finds_listed_urls(conn: SqlitePool) {
+        let state = fixture_state(conn).await;
+
+        assert_eq!(
+            find(
+                &state,
+                "http://malware.testing.google.test/testing/malware/"
+            )
+            .await,
+            Some("MALWARE".to_string())
+        );
+        assert_eq!(
+            find(&state, "https://PHISHING.example.test/login#form").await,
+            Some("SOCIAL_ENGINEERING".to_string())
+        );
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn ignores_unlisted_urls(conn: SqlitePool) {
+        let state = fixture_state(conn).await;
+
+        assert_eq!(find(&state, "https://example.test/").await, None);
+        assert_eq!(find(&state, "https://phishing.example.test/").await, None);
+        assert_eq!(
+            find(&state, "https://phishing.example.test/login/other").await,
+            None
+        );
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn ignores_unconfirmed_prefixes(conn: SqlitePool) {
+        let state = fixture_state(conn).await;
+
+        assert_eq!(
+            find(&state, "https://www.evil.example.test/any/path?query").await,
+            None
+        );
+
+        state
+            .apply_threat_full_hashes(FullHashes { matches: vec![] })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            find(
+                &state,
+                "http://malware.testing.google.test/testing/malware/"
+            )
+            .await,
+            None
+        );
+    }
+
+    #[sqlx::test(migrations = "./src/")]
+    async fn rejects_checksum_mismatches(conn: SqlitePool) {
+        let state = TestDatabase(conn);
+        let updates = FIXTURE.replace("F/1d9qqeqPN2jtuvMFUGivQrzEhTpnc+lkTdtCamzrM=", "AAAA");
+
+        assert!(state
+            .apply_threat_list_updates(serde_json::from_str(&updates).unwrap())
+            .await
+            .is_err());
+
+        state
+            .apply_threat_full_hashes(serde_json::from_str(FULL_HASHES).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            find(
+                &state,
+                "http://malware.testing.google.test/testing/malware/"
+            )
+            .await,
+            None
+        );
+    }
+}
@@ -46,6 +46,7 @@ pub struct User {
     #[redact(partial)]
     pub email: String,
     pub email_verified: bool,
+    pub admin: bool,
     #[redact]
     #[serde(skip_serializing)]
     pub password: String,
@@ -66,6 +67,7 @@ impl From<NewUserCredentials> for User {
             display_name: value.display_name,
             email: value.email,
             email_verified: false,
+            admin: false,
             password: password_hash,
             created_at: now_ts,
             updated_at: now_ts,
@@ -144,6 +146,11 @@ pub trait UserStoreExt {
         &self,
         credentials: UserCredentials,
     ) -> Result<Option<User>, InternalServerError>;
+    async fn set_user_banned(
+        &self,
+        user_id: &Uuid,
+        banned: bool,
+    ) -> Result<bool, InternalServerError>;
 }
 
 #[async_trait::async_trait]
@@ -274,6 +281,7 @@ impl<AppState: Database + Email> UserStoreExt for AppState {
                     display_name,
                     email,
                     email_verified as "email_verified: bool",
+                    admin as "admin: bool",
                     password,
                     created_at as "created_at: DateTime<Utc>",
                     updated_at as "updated_at: DateTime<Utc>"
@@ -310,11 +318,12 @@ impl<AppState: Database + Email> UserStoreExt for AppState {
                     display_name,
                     email,
                     email_verified as "email_verified: bool",
+                    admin as "admin: bool",
                     password,
                     created_at as "created_at: DateTime<Utc>",
                     updated_at as "updated_at: DateTime<Utc>"
                 from user
-                where id = ?
+                where id = ? and banned_at is null
             "#,
             user_id
         )
@@ -337,11 +346,12 @@ impl<AppState: Database + Email> UserStoreExt for AppState {
                     display_name,
                     email,
                     email_verified as "email_verified: bool",
+                    admin as "admin: bool",
                     password,
                     created_at as "created_at: DateTime<Utc>",
                     updated_at as "updated_at: DateTime<Utc>"
                 from user
-                where email = ?
+                where email = ? and banned_at is null
             "#,
             credentials.email
         )
@@ -355,4 +365,58 @@ impl<AppState: Database + Email> UserStoreExt for AppState {
         .await
         .map_err(anyhow::Error::new)?
     }
+
+    async fn set_user_banned(
+        &self,
+        user_id: &Uuid,
+        banned: bool,
+    ) -> Result<bool, InternalServerError> {
+        let now_ms = Utc::now().timestamp_millis();
+        let banned_at = banned.then_some(now_ms);
+
+        let mut tx = self.conn().begin().await.map_err(anyhow::Error::new)?;
+
+        let result = sqlx::query!(
+            r#"
+                update user set banned_at = ?, updated_at = ? where id = ?
+            "#,
+            banned_at,
+            now_ms,
+            user_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::new)?;
+
+        if banned {
+            sqlx::query!(
+                r#"
+                    update url set disabled_at = ?, disabled_reason = 'creator banned', updated_at = ?
+                    where user_id = ? and disabled_at is null
+                "#,
+                now_ms,
+                now_ms,
+                user_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+        } else {
+            sqlx::query!(
+                r#"
+                    update url set disabled_at = null, disabled_reason = null, updated_at = ?
+                    where user_id = ? and disabled_reason = 'creator banned'
+                "#,
+                now_ms,
+                user_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::new)?;
+        }
+
+        tx.commit().await.map_err(anyhow::Error::new)?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
@@ -1,4 +1,18 @@
-use {resend_rs::Resend, sqlx::SqlitePool, std::env};
+use {
+    crate::{
+        routes::RESERVED_KEYS,
+        store_threat_list::ThreatListStoreExt,
+        util_click_recorder::ClickRecorder,
+        util_client_ip::TrustedProxies,
+        util_geoip::GeoIp,
+        util_key_policy::KeyPolicy,
+        util_privacy::{PrivacySettings, SaltCache},
+        util_url_policy::UrlPolicy,
+    },
+    resend_rs::Resend,
+    sqlx::SqlitePool,
+    std::{env, fs, sync::Arc},
+};
 
 pub(crate) trait Database: Send + Sync {
     fn conn(&self) -> &SqlitePool;
@@ -12,10 +26,17 @@ pub(crate) trait Email: Send + Sync {
 pub(crate) struct AppState {
     pub conn: SqlitePool,
     pub email: Resend,
+    pub geoip: GeoIp,
+    pub clicks: ClickRecorder,
+    pub trusted_proxies: Arc<TrustedProxies>,
+    pub privacy: Arc<PrivacySettings>,
+    pub analytics_salt: Arc<SaltCache>,
+    pub key_policy: Arc<KeyPolicy>,
+    pub url_policy: Arc<UrlPolicy>,
 }
 
 impl AppState {
-    pub async fn new() -> Self {
+    pub async fn new(clicks: ClickRecorder) -> Self {
         let conn = SqlitePool::connect(&env::var("DATABASE_URL").unwrap())
             .await
             .unwrap();
@@ -25,8 +46,30 @@ impl AppState {
         let app_state = Self {
             conn,
             email: Resend::default(),
+            geoip: GeoIp::from_env(),
+            clicks,
+            trusted_proxies: Arc::new(TrustedProxies::from_env()),
+            privacy: Arc::new(PrivacySettings::from_env()),
+            analytics_salt: Arc::new(SaltCache::default()),
+            key_policy: Arc::new(KeyPolicy::from_env(RESERVED_KEYS)),
+            url_policy: Arc::new(UrlPolicy::from_env()),
         };
 
+        if let Ok(path) = env::var("THREAT_LIST_UPDATES") {
+            let updates = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+
+            app_state.apply_threat_list_updates(updates).await.unwrap();
+        }
+
+        if let Ok(path) = env::var("THREAT_FULL_HASHES") {
+            let full_hashes = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+
+            app_state
+                .apply_threat_full_hashes(full_hashes)
+                .await
+                .unwrap();
+        }
+
         app_state
     }
 }
@@ -38,6 +81,16 @@ impl Database for AppState {
     }
 }
 
+#[cfg(test)]
+pub(crate) struct TestDatabase(pub SqlitePool);
+
+#[cfg(test)]
+impl Database for TestDatabase {
+    fn conn(&self) -> &SqlitePool {
+        &self.0
+    }
+}
+
 impl Email for AppState {
     #[inline]
     fn email(&self) -> &Resend {
@@ -5,7 +5,11 @@ use {
         AppState,
     },
     async_trait::async_trait,
-    axum_login::{AuthUser, AuthnBackend, UserId},
+    axum::{
+        extract::FromRequestParts,
+        http::{request::Parts, StatusCode},
+    },
+    axum_login::{AuthSession, AuthUser, AuthnBackend, UserId},
     uuid::Uuid,
 };
 
@@ -42,3 +46,26 @@ impl AuthnBackend for AppState {
         Ok(user)
     }
 }
+
+#[derive(Clone)]
+pub struct Admin(pub User);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let auth_session = AuthSession::<AppState>::from_request_parts(parts, state)
+            .await
+            .map_err(|(status, _)| status)?;
+
+        match auth_session.user {
+            Some(user) if user.admin => Ok(Self(user)),
+            Some(_) => Err(StatusCode::FORBIDDEN),
+            None => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use {
+    crate::{
+        store_analytics::{AnalyticsStoreExt, ClickEvent},
+        AppState,
+    },
+    std::{
+        env,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::{
+        sync::{mpsc, Notify},
+        time,
+    },
+    tracing::{error, info, warn},
+};
+
+const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+const BATCH_SIZE: usize = 500;
+const FLUSH_ATTEMPTS: u32 = 3;
+const FLUSH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug)]
+pub struct ClickRecorder {
+    sender: mpsc::Sender<ClickEvent>,
+    dropped: Arc<AtomicU64>,
+    shutdown: Arc<Notify>,
+}
+
+impl ClickRecorder {
+    pub fn from_env() -> (Self, mpsc::Receiver<ClickEvent>) {
+        let capacity = env::var("CLICK_QUEUE_CAPACITY")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(DEFAULT_QUEUE_CAPACITY);
+
+        assert!(capacity >= 1, "CLICK_QUEUE_CAPACITY must be at least 1");
+
+        let (sender, receiver) = mpsc::channel(capacity);
+
+        let recorder = Self {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+            shutdown: Arc::new(Notify::new()),
+        };
+
+        (recorder, receiver)
+    }
+
+    pub fn record(&self, click: ClickEvent) {
+        if self.sender.try_send(click).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.notify_one();
+    }
+}
+
+async fn flush(state: &AppState, batch: &mut Vec<ClickEvent>) {
+    for attempt in 1..=FLUSH_ATTEMPTS {
+        match state.record_clicks(batch).await {
+            Ok(()) => {
+                batch.clear();
+                return;
+            }
+            Err(err) if attempt < FLUSH_ATTEMPTS => {
+                warn!("failed to flush click events, retrying: {:?}", err);
+                time::sleep(FLUSH_RETRY_DELAY).await;
+            }
+            Err(err) => {
+                error!("failed to flush click events: {:?}", err);
+            }
+        }
+    }
+
+    state
+        .clicks
+        .dropped
+        .fetch_add(batch.len() as u64, Ordering::Relaxed);
+    batch.clear();
+}
+
+fn report_dropped(recorder: &ClickRecorder, reported: u64) -> u64 {
+    let dropped = recorder.dropped();
+
+    if dropped > reported {
+        warn!(
+            "dropped {} click events, {} in total",
+            dropped - reported,
+            dropped
+        );
+    }
+
+    dropped
+}
+
+pub async fn run(state: AppState, mut receiver: mpsc::Receiver<ClickEvent>) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut reported = 0;
+
+    loop {
+        tokio::select! {
+            _ = state.clicks.shutdown.notified() => break,
+            received = receiver.recv_many(&mut batch, BATCH_SIZE) => {
+                if received == 0 {
+                    break;
+                }
+
+                flush(&state, &mut batch).await;
+                reported = report_dropped(&state.clicks, reported);
+            }
+        }
+    }
+
+    receiver.close();
+
+    while receiver.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        flush(&state, &mut batch).await;
+    }
+
+    info!(
+        "click recorder drained, {} click events dropped",
+        state.clicks.dropped()
+    );
+}
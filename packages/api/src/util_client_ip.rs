@@ -0,0 +1,256 @@
+use {
+    crate::AppState,
+    async_trait::async_trait,
+    axum::{
+        extract::{rejection::ExtensionRejection, ConnectInfo, FromRequestParts},
+        http::{request::Parts, HeaderMap},
+    },
+    ipnet::IpNet,
+    std::{
+        env,
+        net::{IpAddr, SocketAddr},
+    },
+};
+
+const FORWARDED: &str = "forwarded";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_REAL_IP: &str = "x-real-ip";
+
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies {
+    networks: Vec<IpNet>,
+}
+
+fn parse_network(value: &str) -> IpNet {
+    value
+        .parse()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .unwrap()
+}
+
+fn parse_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+
+    if let Some(value) = value.strip_prefix('[') {
+        return value.split_once(']')?.0.parse().ok();
+    }
+
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+}
+
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_values(headers, FORWARDED)
+        .map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("for"))
+                .and_then(|(_, value)| parse_ip(value))
+        })
+        .collect()
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_values(headers, X_FORWARDED_FOR)
+        .map(parse_ip)
+        .collect()
+}
+
+impl TrustedProxies {
+    pub fn from_env() -> Self {
+        let networks = env::var("TRUSTED_PROXIES")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(parse_network)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { networks }
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+
+        self.networks.iter().any(|network| network.contains(&ip))
+    }
+
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = peer.to_canonical();
+
+        if !self.contains(&peer) {
+            return peer;
+        }
+
+        let mut chain = forwarded_for(headers);
+
+        if chain.is_empty() {
+            chain = x_forwarded_for(headers);
+        }
+
+        if chain.is_empty() {
+            return header_values(headers, X_REAL_IP)
+                .next()
+                .and_then(parse_ip)
+                .map_or(peer, |ip| ip.to_canonical());
+        }
+
+        let mut client = peer;
+
+        for hop in chain.into_iter().rev() {
+            match hop.map(|ip| ip.to_canonical()) {
+                Some(ip) if self.contains(&ip) => client = ip,
+                Some(ip) => return ip,
+                None => return client,
+            }
+        }
+
+        client
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(addr) = ConnectInfo::<SocketAddr>::from_request_parts(parts, state).await?;
+
+        Ok(Self(
+            state.trusted_proxies.resolve(addr.ip(), &parts.headers),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, axum::http::HeaderValue};
+
+    fn proxies() -> TrustedProxies {
+        TrustedProxies {
+            networks: vec![parse_network("10.0.0.0/8"), parse_network("2001:db8::1")],
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let headers = headers(&[
+            (FORWARDED, "for=198.51.100.1"),
+            (X_FORWARDED_FOR, "198.51.100.2"),
+            (X_REAL_IP, "198.51.100.3"),
+        ]);
+
+        assert_eq!(
+            proxies().resolve(ip("203.0.113.9"), &headers),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn walks_x_forwarded_for_from_the_right() {
+        let spoofed = headers(&[(X_FORWARDED_FOR, "192.0.2.1, 198.51.100.2, 10.0.0.2")]);
+        let trusted = headers(&[(X_FORWARDED_FOR, "10.0.0.3"), (X_FORWARDED_FOR, "10.0.0.2")]);
+
+        assert_eq!(
+            proxies().resolve(ip("10.0.0.1"), &spoofed),
+            ip("198.51.100.2")
+        );
+        assert_eq!(proxies().resolve(ip("10.0.0.1"), &trusted), ip("10.0.0.3"));
+    }
+
+    #[test]
+    fn parses_forwarded_addresses_with_ports() {
+        let headers = headers(&[
+            (
+                FORWARDED,
+                r#"for="[2001:db8:cafe::17]:4711";proto=https, for=10.0.0.2"#,
+            ),
+            (X_FORWARDED_FOR, "198.51.100.2"),
+        ]);
+
+        assert_eq!(
+            proxies().resolve(ip("2001:db8::1"), &headers),
+            ip("2001:db8:cafe::17")
+        );
+    }
+
+    #[test]
+    fn canonicalises_ipv4_mapped_peers() {
+        let headers = headers(&[(X_FORWARDED_FOR, "198.51.100.2")]);
+
+        assert_eq!(
+            proxies().resolve(ip("::ffff:10.0.0.1"), &headers),
+            ip("198.51.100.2")
+        );
+        assert_eq!(
+            proxies().resolve(ip("::ffff:203.0.113.9"), &headers),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn stops_at_malformed_hops() {
+        let peer = ip("10.0.0.1");
+
+        assert_eq!(
+            proxies().resolve(
+                peer,
+                &headers(&[(X_FORWARDED_FOR, "198.51.100.2, garbage")])
+            ),
+            peer
+        );
+        assert_eq!(
+            proxies().resolve(
+                peer,
+                &headers(&[(X_FORWARDED_FOR, "198.51.100.2, garbage, 10.0.0.2")])
+            ),
+            ip("10.0.0.2")
+        );
+        assert_eq!(
+            proxies().resolve(peer, &headers(&[(FORWARDED, "for=unknown")])),
+            peer
+        );
+        assert_eq!(
+            proxies().resolve(peer, &headers(&[(FORWARDED, "for=_hidden;by=10.0.0.2")])),
+            peer
+        );
+        assert_eq!(
+            proxies().resolve(peer, &headers(&[(X_REAL_IP, "not an ip")])),
+            peer
+        );
+    }
+}
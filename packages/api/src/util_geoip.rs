@@ -0,0 +1,26 @@
+use {
+    maxminddb::{geoip2, Reader},
+    std::{env, net::IpAddr, sync::Arc},
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct GeoIp {
+    reader: Option<Arc<Reader<Vec<u8>>>>,
+}
+
+impl GeoIp {
+    pub fn from_env() -> Self {
+        let reader = env::var("GEOIP_DATABASE")
+            .ok()
+            .map(|path| Arc::new(Reader::open_readfile(path).unwrap()));
+
+        Self { reader }
+    }
+
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let reader = self.reader.as_ref()?;
+        let country: geoip2::Country = reader.lookup(ip).ok()?;
+
+        country.country?.iso_code.map(str::to_string)
+    }
+}
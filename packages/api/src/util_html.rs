@@ -0,0 +1,49 @@
+use {
+    axum::response::{IntoResponse, Response},
+    hyper::{header, StatusCode},
+};
+
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for char in value.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+pub fn page(status: StatusCode, title: &str, body: &str) -> Response {
+    let title = escape(title);
+
+    (
+        status,
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+        format!(
+            r#"<!doctype html>
+<html lang="en">
+    <head>
+        <meta charset="utf-8" />
+        <meta name="viewport" content="width=device-width, initial-scale=1" />
+        <meta name="robots" content="noindex" />
+        <title>{title} | itty.pro</title>
+    </head>
+    <body>
+        <main>
+            <h1>{title}</h1>
+            {body}
+        </main>
+    </body>
+</html>
+"#
+        ),
+    )
+        .into_response()
+}
@@ -0,0 +1,122 @@
+use {
+    crate::store_link::LinkError,
+    nanoid::nanoid,
+    std::{env, str::FromStr},
+    tracing::warn,
+};
+
+const DEFAULT_GENERATED_LENGTH: usize = 8;
+const DEFAULT_MIN_LENGTH: usize = 1;
+const DEFAULT_MAX_LENGTH: usize = 64;
+const FORBIDDEN_CHARACTERS: &[char] = &['/', '+', '?', '#', '%'];
+
+#[derive(Clone, Debug)]
+pub struct KeyPolicy {
+    alphabet: Vec<char>,
+    allowed_characters: Vec<char>,
+    generated_length: usize,
+    min_length: usize,
+    max_length: usize,
+    reserved: Vec<String>,
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    match env::var(name).map(|value| value.parse()) {
+        Ok(Ok(value)) => value,
+        Ok(Err(_)) => {
+            warn!("{name} is not a valid number, using the default");
+            default
+        }
+        Err(_) => default,
+    }
+}
+
+fn env_characters(name: &str) -> Option<Vec<char>> {
+    env::var(name).ok().map(|value| {
+        let mut characters: Vec<char> = value.chars().collect();
+        characters.sort_unstable();
+        characters.dedup();
+        characters
+    })
+}
+
+impl KeyPolicy {
+    pub fn from_env(reserved: &[&str]) -> Self {
+        let alphabet =
+            env_characters("KEY_ALPHABET").unwrap_or_else(|| nanoid::alphabet::SAFE.to_vec());
+        let allowed_characters = env_characters("KEY_ALLOWED_CHARACTERS")
+            .unwrap_or_else(|| nanoid::alphabet::SAFE.to_vec());
+
+        let policy = Self {
+            alphabet,
+            allowed_characters,
+            generated_length: env_or("KEY_LENGTH", DEFAULT_GENERATED_LENGTH),
+            min_length: env_or("KEY_MIN_LENGTH", DEFAULT_MIN_LENGTH),
+            max_length: env_or("KEY_MAX_LENGTH", DEFAULT_MAX_LENGTH),
+            reserved: reserved.iter().map(|key| key.to_lowercase()).collect(),
+        };
+
+        assert!(
+            (2..=u8::MAX as usize).contains(&policy.alphabet.len()),
+            "KEY_ALPHABET must contain between 2 and 255 characters"
+        );
+        assert!(
+            policy
+                .alphabet
+                .iter()
+                .all(|c| policy.allowed_characters.contains(c)),
+            "KEY_ALPHABET must be a subset of KEY_ALLOWED_CHARACTERS"
+        );
+        assert!(
+            !policy
+                .allowed_characters
+                .iter()
+                .any(|c| c.is_whitespace() || c.is_control() || FORBIDDEN_CHARACTERS.contains(c)),
+            "KEY_ALLOWED_CHARACTERS must not contain whitespace, control characters or any of {FORBIDDEN_CHARACTERS:?}"
+        );
+        assert!(
+            0 < policy.min_length && policy.min_length <= policy.max_length,
+            "KEY_MIN_LENGTH must be between 1 and KEY_MAX_LENGTH"
+        );
+        assert!(
+            (policy.min_length..=policy.max_length).contains(&policy.generated_length),
+            "KEY_LENGTH must be between KEY_MIN_LENGTH and KEY_MAX_LENGTH"
+        );
+
+        policy
+    }
+
+    pub fn generate(&self) -> String {
+        nanoid!(self.generated_length, &self.alphabet)
+    }
+
+    pub fn validate(&self, key: &str) -> Result<(), LinkError> {
+        let length = key.chars().count();
+
+        if length < self.min_length {
+            return Err(LinkError::KeyTooShort {
+                min_length: self.min_length,
+            });
+        }
+
+        if length > self.max_length {
+            return Err(LinkError::KeyTooLong {
+                max_length: self.max_length,
+            });
+        }
+
+        if !key.chars().all(|c| self.allowed_characters.contains(&c)) {
+            return Err(LinkError::KeyInvalidCharacters {
+                allowed_characters: self.allowed_characters.iter().collect(),
+            });
+        }
+
+        if self.reserved.contains(&key.to_lowercase()) {
+            return Err(LinkError::KeyReserved {
+                key: key.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
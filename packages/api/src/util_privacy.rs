@@ -0,0 +1,170 @@
+use {
+    crate::{store_analytics::AnalyticsStoreExt, util_app_error::InternalServerError, AppState},
+    chrono::{DateTime, NaiveTime, Utc},
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{
+        env,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        str::FromStr,
+        sync::RwLock,
+    },
+    utoipa::ToSchema,
+};
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum IpMode {
+    #[default]
+    Raw,
+    Truncated,
+    Hashed,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum RetentionAction {
+    #[default]
+    Delete,
+    Rollup,
+}
+
+impl FromStr for IpMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "raw" => Ok(IpMode::Raw),
+            "truncated" => Ok(IpMode::Truncated),
+            "hashed" => Ok(IpMode::Hashed),
+            _ => Err(format!("unknown ip mode {value}")),
+        }
+    }
+}
+
+impl FromStr for RetentionAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "delete" => Ok(RetentionAction::Delete),
+            "rollup" => Ok(RetentionAction::Rollup),
+            _ => Err(format!("unknown retention action {value}")),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, ToSchema)]
+pub struct PrivacySettings {
+    pub ip_mode: Option<IpMode>,
+    #[schema(minimum = 1)]
+    pub retention_days: Option<i64>,
+    pub retention_action: Option<RetentionAction>,
+}
+
+impl PrivacySettings {
+    pub fn from_env() -> Self {
+        let settings = Self {
+            ip_mode: env::var("ANALYTICS_IP_MODE")
+                .ok()
+                .map(|value| value.parse().unwrap()),
+            retention_days: env::var("ANALYTICS_RETENTION_DAYS")
+                .ok()
+                .map(|value| value.parse().unwrap()),
+            retention_action: env::var("ANALYTICS_RETENTION_ACTION")
+                .ok()
+                .map(|value| value.parse().unwrap()),
+        };
+
+        assert!(
+            settings.is_valid(),
+            "ANALYTICS_RETENTION_DAYS must be at least 1"
+        );
+
+        settings
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.retention_days.is_none_or(|days| days >= 1)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SaltCache {
+    current: RwLock<Option<(i64, Vec<u8>)>>,
+}
+
+impl SaltCache {
+    pub fn get(&self, day: i64) -> Option<Vec<u8>> {
+        match &*self.current.read().unwrap() {
+            Some((cached_day, salt)) if *cached_day == day => Some(salt.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn set(&self, day: i64, salt: Vec<u8>) {
+        *self.current.write().unwrap() = Some((day, salt));
+    }
+}
+
+pub fn truncate_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, ..] = ip.segments();
+
+            IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+        }
+    }
+}
+
+pub fn hash_ip(salt: &[u8], ip: IpAddr, user_agent: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt)
+        .chain_update(ip.to_string())
+        .chain_update([0])
+        .chain_update(user_agent)
+        .finalize();
+
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub async fn anonymise_ip(
+    state: &AppState,
+    ip_mode: IpMode,
+    ip: IpAddr,
+    user_agent: &str,
+    now_ts: DateTime<Utc>,
+) -> Result<String, InternalServerError> {
+    match ip_mode {
+        IpMode::Raw => Ok(ip.to_string()),
+        IpMode::Truncated => Ok(truncate_ip(ip).to_string()),
+        IpMode::Hashed => {
+            let today = now_ts
+                .date_naive()
+                .and_time(NaiveTime::MIN)
+                .and_utc()
+                .timestamp_millis();
+            let salt = match state.analytics_salt.get(today) {
+                Some(salt) => salt,
+                None => {
+                    let salt = state.get_analytics_salt(today).await?;
+                    state.analytics_salt.set(today, salt.clone());
+                    salt
+                }
+            };
+
+            Ok(hash_ip(&salt, ip, user_agent))
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use {
+    axum::{response::IntoResponse, Json},
+    hyper::StatusCode,
+    image::{ImageFormat, Rgba, RgbaImage},
+    qrcode::{EcLevel, QrCode},
+    serde::{Deserialize, Serialize},
+    std::{fmt::Write, io::Cursor},
+    thiserror::Error,
+    utoipa::ToSchema,
+};
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    Png,
+    #[default]
+    Svg,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+pub enum QrErrorCorrection {
+    L,
+    #[default]
+    M,
+    Q,
+    H,
+}
+
+impl From<QrErrorCorrection> for EcLevel {
+    fn from(value: QrErrorCorrection) -> Self {
+        match value {
+            QrErrorCorrection::L => EcLevel::L,
+            QrErrorCorrection::M => EcLevel::M,
+            QrErrorCorrection::Q => EcLevel::Q,
+            QrErrorCorrection::H => EcLevel::H,
+        }
+    }
+}
+
+#[derive(Error, Debug, Serialize, ToSchema)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum QrError {
+    #[error("colour must be a six digit hex code")]
+    InvalidColour { colour: String },
+    #[error("data does not fit in a qr code")]
+    DataTooLong,
+    #[error(transparent)]
+    InternalServerError {
+        #[serde(skip)]
+        #[from]
+        error: anyhow::Error,
+    },
+}
+
+impl Into<StatusCode> for &QrError {
+    fn into(self) -> StatusCode {
+        match self {
+            QrError::InvalidColour { colour: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            QrError::DataTooLong => StatusCode::UNPROCESSABLE_ENTITY,
+            QrError::InternalServerError { error: _ } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for QrError {
+    fn into_response(self) -> axum::response::Response {
+        (Into::<StatusCode>::into(&self), Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Colour([u8; 3]);
+
+impl Colour {
+    pub const BLACK: Self = Self([0x00, 0x00, 0x00]);
+    pub const WHITE: Self = Self([0xff, 0xff, 0xff]);
+
+    pub fn parse(value: &str) -> Result<Self, QrError> {
+        let hex = value.trim_start_matches('#');
+        let invalid = || QrError::InvalidColour {
+            colour: value.to_string(),
+        };
+
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16);
+
+        Ok(Self([
+            channel(0).map_err(|_| invalid())?,
+            channel(2).map_err(|_| invalid())?,
+            channel(4).map_err(|_| invalid())?,
+        ]))
+    }
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0[0], self.0[1], self.0[2])
+    }
+
+    fn rgba(&self) -> Rgba<u8> {
+        Rgba([self.0[0], self.0[1], self.0[2], 0xff])
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QrOptions {
+    pub size: u32,
+    pub margin: u32,
+    pub error_correction: QrErrorCorrection,
+    pub foreground: Colour,
+    pub background: Colour,
+}
+
+struct QrMatrix {
+    modules: Vec<bool>,
+    width: u32,
+    scale: u32,
+    dimension: u32,
+}
+
+impl QrMatrix {
+    fn new(data: &str, options: &QrOptions) -> Result<Self, QrError> {
+        let code = QrCode::with_error_correction_level(data, options.error_correction.into())
+            .map_err(|_| QrError::DataTooLong)?;
+
+        let width = code.width() as u32;
+        let modules_with_margin = width + options.margin * 2;
+        let scale = (options.size / modules_with_margin).max(1);
+
+        Ok(Self {
+            modules: code.to_vec(),
+            width,
+            scale,
+            dimension: modules_with_margin * scale,
+        })
+    }
+
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        self.modules[(y * self.width + x) as usize]
+    }
+}
+
+pub fn render_svg(data: &str, options: &QrOptions) -> Result<String, QrError> {
+    let matrix = QrMatrix::new(data, options)?;
+    let view_box = matrix.width + options.margin * 2;
+
+    let mut path = String::new();
+
+    for y in 0..matrix.width {
+        for x in 0..matrix.width {
+            if matrix.is_dark(x, y) {
+                write!(
+                    path,
+                    "M{},{}h1v1h-1z",
+                    x + options.margin,
+                    y + options.margin
+                )
+                .map_err(anyhow::Error::new)?;
+            }
+        }
+    }
+
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{dimension}" height="{dimension}" viewBox="0 0 {view_box} {view_box}" shape-rendering="crispEdges"><rect width="100%" height="100%" fill="{background}"/><path d="{path}" fill="{foreground}"/></svg>
+"#,
+        dimension = matrix.dimension,
+        background = options.background.hex(),
+        foreground = options.foreground.hex(),
+    ))
+}
+
+pub fn render_png(data: &str, options: &QrOptions) -> Result<Vec<u8>, QrError> {
+    let matrix = QrMatrix::new(data, options)?;
+
+    let mut image = RgbaImage::from_pixel(
+        matrix.dimension,
+        matrix.dimension,
+        options.background.rgba(),
+    );
+
+    for y in 0..matrix.width {
+        for x in 0..matrix.width {
+            if matrix.is_dark(x, y) {
+                let left = (x + options.margin) * matrix.scale;
+                let top = (y + options.margin) * matrix.scale;
+
+                for dy in 0..matrix.scale {
+                    for dx in 0..matrix.scale {
+                        image.put_pixel(left + dx, top + dy, options.foreground.rgba());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut png = Vec::new();
+
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(anyhow::Error::new)?;
+
+    Ok(png)
+}
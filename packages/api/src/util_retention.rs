@@ -0,0 +1,20 @@
+use {
+    crate::{store_analytics::AnalyticsStoreExt, AppState},
+    std::time::Duration,
+    tokio::time,
+    tracing::error,
+};
+
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub async fn run(state: AppState) {
+    let mut interval = time::interval(RETENTION_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = state.apply_retention(&state.privacy).await {
+            error!("failed to apply analytics retention: {:?}", err);
+        }
+    }
+}
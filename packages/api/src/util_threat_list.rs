@@ -0,0 +1,286 @@
+use {
+    serde::Deserialize,
+    sha2::{Digest, Sha256},
+    url::{Host, Url},
+};
+
+const MAX_HOST_SUFFIXES: usize = 4;
+const MAX_PATH_PREFIXES: usize = 4;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreatListUpdates {
+    #[serde(default)]
+    pub list_update_responses: Vec<ListUpdateResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ResponseType {
+    ResponseTypeUnspecified,
+    PartialUpdate,
+    FullUpdate,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUpdateResponse {
+    pub threat_type: String,
+    pub threat_entry_type: String,
+    pub platform_type: String,
+    pub response_type: ResponseType,
+    #[serde(default)]
+    pub additions: Vec<ThreatEntrySet>,
+    #[serde(default)]
+    pub removals: Vec<ThreatEntrySet>,
+    pub new_client_state: Option<String>,
+    pub checksum: Option<Checksum>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreatEntrySet {
+    pub compression_type: Option<String>,
+    pub raw_hashes: Option<RawHashes>,
+    pub raw_indices: Option<RawIndices>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawHashes {
+    pub prefix_size: usize,
+    pub raw_hashes: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawIndices {
+    #[serde(default)]
+    pub indices: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checksum {
+    pub sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullHashes {
+    #[serde(default)]
+    pub matches: Vec<ThreatMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreatMatch {
+    pub threat_type: String,
+    pub platform_type: String,
+    pub threat_entry_type: String,
+    pub threat: ThreatEntry,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreatEntry {
+    pub hash: String,
+}
+
+fn unescape(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+
+    loop {
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+
+        while index < bytes.len() {
+            let hex = bytes
+                .get(index + 1..index + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+            match (bytes[index], hex) {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    index += 3;
+                }
+                (byte, _) => {
+                    decoded.push(byte);
+                    index += 1;
+                }
+            }
+        }
+
+        if decoded == bytes {
+            return bytes;
+        }
+
+        bytes = decoded;
+    }
+}
+
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+
+    for &byte in bytes {
+        if byte <= 0x20 || byte >= 0x7f || byte == b'#' || byte == b'%' {
+            escaped.push_str(&format!("%{byte:02X}"));
+        } else {
+            escaped.push(byte as char);
+        }
+    }
+
+    escaped
+}
+
+fn canonical_path(url: &Url) -> String {
+    let path = escape(&unescape(url.path()));
+    let mut canonical = String::with_capacity(path.len());
+
+    for char in path.chars() {
+        if !(char == '/' && canonical.ends_with('/')) {
+            canonical.push(char);
+        }
+    }
+
+    canonical
+}
+
+fn host_suffixes(url: &Url) -> Vec<String> {
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            let labels: Vec<&str> = domain
+                .split('.')
+                .filter(|label| !label.is_empty())
+                .collect();
+            let host = labels.join(".");
+            let start = labels.len().saturating_sub(MAX_HOST_SUFFIXES + 1).max(1);
+
+            let mut suffixes = vec![host];
+
+            for index in start..labels.len().saturating_sub(1) {
+                suffixes.push(labels[index..].join("."));
+            }
+
+            suffixes
+        }
+        Some(host) => vec![host.to_string()],
+        None => vec![],
+    }
+}
+
+fn path_prefixes(url: &Url) -> Vec<String> {
+    let path = canonical_path(url);
+    let mut prefixes = Vec::new();
+
+    if let Some(query) = url.query() {
+        prefixes.push(format!("{path}?{}", escape(&unescape(query))));
+    }
+
+    prefixes.push(path.clone());
+
+    let mut prefix = String::from("/");
+    let mut directories = path
+        .trim_start_matches('/')
+        .split('/')
+        .rev()
+        .skip(1)
+        .collect::<Vec<_>>();
+
+    for _ in 0..MAX_PATH_PREFIXES {
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix.clone());
+        }
+
+        match directories.pop() {
+            Some(directory) => {
+                prefix.push_str(directory);
+                prefix.push('/');
+            }
+            None => break,
+        }
+    }
+
+    prefixes
+}
+
+pub fn expressions(url: &Url) -> Vec<String> {
+    let paths = path_prefixes(url);
+
+    host_suffixes(url)
+        .into_iter()
+        .flat_map(|host| paths.iter().map(move |path| format!("{host}{path}")))
+        .collect()
+}
+
+pub fn full_hashes(url: &Url) -> Vec<Vec<u8>> {
+    expressions(url)
+        .into_iter()
+        .map(|expression| Sha256::digest(expression.as_bytes()).to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(url: &str) -> String {
+        expressions(&Url::parse(url).unwrap()).remove(0)
+    }
+
+    #[test]
+    fn canonicalises_urls() {
+        assert_eq!(canonical("http://host/%25%32%35"), "host/%25");
+        assert_eq!(canonical("http://host/%25%32%35%25%32%35"), "host/%25%25");
+        assert_eq!(canonical("http://host/%2525252525252525"), "host/%25");
+        assert_eq!(canonical("http://host/asdf%25%32%35asd"), "host/asdf%25asd");
+        assert_eq!(canonical("http://www.GOOgle.com/"), "www.google.com/");
+        assert_eq!(
+            canonical("http://www.google.com/blah/.."),
+            "www.google.com/"
+        );
+        assert_eq!(canonical("http://www.google.com/#frag"), "www.google.com/");
+        assert_eq!(
+            canonical("http://host.com//twoslashes?more//slashes"),
+            "host.com/twoslashes?more//slashes"
+        );
+        assert_eq!(canonical("http://3279880203/blah"), "195.127.0.11/blah");
+    }
+
+    #[test]
+    fn builds_host_and_path_expressions() {
+        assert_eq!(
+            expressions(&Url::parse("http://a.b.c/1/2.html?param=1").unwrap()),
+            [
+                "a.b.c/1/2.html?param=1",
+                "a.b.c/1/2.html",
+                "a.b.c/",
+                "a.b.c/1/",
+                "b.c/1/2.html?param=1",
+                "b.c/1/2.html",
+                "b.c/",
+                "b.c/1/",
+            ]
+        );
+        assert_eq!(
+            expressions(&Url::parse("http://a.b.c.d.e.f.g/1.html").unwrap()),
+            [
+                "a.b.c.d.e.f.g/1.html",
+                "a.b.c.d.e.f.g/",
+                "c.d.e.f.g/1.html",
+                "c.d.e.f.g/",
+                "d.e.f.g/1.html",
+                "d.e.f.g/",
+                "e.f.g/1.html",
+                "e.f.g/",
+                "f.g/1.html",
+                "f.g/",
+            ]
+        );
+        assert_eq!(
+            expressions(&Url::parse("http://1.2.3.4/1/").unwrap()),
+            ["1.2.3.4/1/", "1.2.3.4/"]
+        );
+    }
+}
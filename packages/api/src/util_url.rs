@@ -0,0 +1,116 @@
+use url::{form_urlencoded, Url};
+
+fn segment_name(segment: &str) -> String {
+    let name = segment.split_once('=').map_or(segment, |(name, _)| name);
+
+    form_urlencoded::parse(name.as_bytes())
+        .next()
+        .map(|(name, _)| name.into_owned())
+        .unwrap_or_default()
+}
+
+fn encode_pairs<'a>(
+    pairs: &'a [(String, String)],
+    name: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    pairs
+        .iter()
+        .filter(move |(pair, _)| pair == name)
+        .map(|(name, value)| {
+            form_urlencoded::Serializer::new(String::new())
+                .append_pair(name, value)
+                .finish()
+        })
+}
+
+pub fn merge_query_pairs(url: &mut Url, pairs: Vec<(String, String)>) {
+    if pairs.is_empty() {
+        return;
+    }
+
+    let mut merged: Vec<String> = Vec::new();
+    let mut segments = Vec::new();
+
+    for segment in url
+        .query()
+        .filter(|query| !query.is_empty())
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+    {
+        let name = segment_name(segment);
+
+        if !pairs.iter().any(|(pair, _)| *pair == name) {
+            segments.push(segment.to_string());
+        } else if !merged.contains(&name) {
+            segments.extend(encode_pairs(&pairs, &name));
+            merged.push(name);
+        }
+    }
+
+    for (name, _) in &pairs {
+        if !merged.contains(name) {
+            segments.extend(encode_pairs(&pairs, name));
+            merged.push(name.clone());
+        }
+    }
+
+    url.set_query(Some(&segments.join("&")));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(url: &str, pairs: &[(&str, &str)]) -> String {
+        let mut url = Url::parse(url).unwrap();
+
+        merge_query_pairs(
+            &mut url,
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        );
+
+        url.to_string()
+    }
+
+    #[test]
+    fn keeps_the_existing_query_untouched() {
+        assert_eq!(
+            merge(
+                "https://example.com/?a=1&flag&sig=ab%2Fc+d&z=",
+                &[("utm_source", "news letter")]
+            ),
+            "https://example.com/?a=1&flag&sig=ab%2Fc+d&z=&utm_source=news+letter"
+        );
+    }
+
+    #[test]
+    fn replaces_overridden_pairs_in_place() {
+        assert_eq!(
+            merge("https://example.com/?a=1&b=2&a=3&c", &[("a", "x")]),
+            "https://example.com/?a=x&b=2&c"
+        );
+    }
+
+    #[test]
+    fn appends_to_an_empty_query() {
+        assert_eq!(
+            merge("https://example.com/path?", &[("a", "1"), ("a", "2")]),
+            "https://example.com/path?a=1&a=2"
+        );
+        assert_eq!(
+            merge("https://example.com/path", &[("a", "1")]),
+            "https://example.com/path?a=1"
+        );
+    }
+
+    #[test]
+    fn leaves_urls_without_pairs_alone() {
+        assert_eq!(
+            merge("https://example.com/?sig=a%20b", &[]),
+            "https://example.com/?sig=a%20b"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use {
+    crate::store_link::LinkError,
+    std::{env, fs},
+    url::Url,
+};
+
+const DEFAULT_ALLOWED_SCHEMES: &str = "http,https";
+
+#[derive(Clone, Debug)]
+pub struct UrlPolicy {
+    allowed_schemes: Vec<String>,
+    blocklist: Vec<String>,
+    allowlist: Option<Vec<String>>,
+}
+
+fn read_domain_list(path: &str) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.trim_start_matches("*.").to_lowercase())
+        .collect()
+}
+
+fn matches_domain(host: &str, domains: &[String]) -> bool {
+    domains.iter().any(|domain| {
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|subdomain| subdomain.ends_with('.'))
+    })
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    }
+}
+
+impl UrlPolicy {
+    pub fn from_env() -> Self {
+        let allowed_schemes = env::var("URL_ALLOWED_SCHEMES")
+            .unwrap_or(DEFAULT_ALLOWED_SCHEMES.to_string())
+            .split(',')
+            .map(|scheme| scheme.trim().to_lowercase())
+            .filter(|scheme| !scheme.is_empty())
+            .collect();
+
+        Self {
+            allowed_schemes,
+            blocklist: env::var("URL_DOMAIN_BLOCKLIST")
+                .map(|path| read_domain_list(&path))
+                .unwrap_or_default(),
+            allowlist: env::var("URL_DOMAIN_ALLOWLIST")
+                .ok()
+                .map(|path| read_domain_list(&path)),
+        }
+    }
+
+    pub fn check(&self, url: &Url, own_host: &str) -> Result<(), LinkError> {
+        if !self
+            .allowed_schemes
+            .iter()
+            .any(|scheme| scheme == url.scheme())
+        {
+            return Err(LinkError::UrlSchemeNotAllowed {
+                scheme: url.scheme().to_string(),
+            });
+        }
+
+        let host = match url.host_str() {
+            Some(host) => host.trim_end_matches('.').to_lowercase(),
+            None => return Err(LinkError::UrlMissingHost),
+        };
+
+        if host == strip_port(own_host).to_lowercase() {
+            return Err(LinkError::UrlRedirectLoop);
+        }
+
+        if matches_domain(&host, &self.blocklist) {
+            return Err(LinkError::UrlDomainBlocked { domain: host });
+        }
+
+        if let Some(allowlist) = &self.allowlist {
+            if !matches_domain(&host, allowlist) {
+                return Err(LinkError::UrlDomainNotAllowed { domain: host });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+const SOCIAL_CRAWLERS: &[&str] = &[
+    "facebookexternalhit",
+    "Facebot",
+    "Twitterbot",
+    "LinkedInBot",
+    "Slackbot",
+    "Discordbot",
+    "TelegramBot",
+    "WhatsApp",
+    "Pinterestbot",
+    "redditbot",
+    "SkypeUriPreview",
+    "Embedly",
+    "vkShare",
+    "Mastodon",
+];
+
+pub fn is_social_crawler(user_agent: &str) -> bool {
+    SOCIAL_CRAWLERS
+        .iter()
+        .any(|crawler| user_agent.contains(crawler))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Ios,
+    Android,
+    Desktop,
+    Other,
+}
+
+impl Platform {
+    // iPadOS requests desktop sites with a Macintosh user agent that is indistinguishable from
+    // macOS Safari, so iPads are only detected as iOS when they ask for the mobile site.
+    pub fn from_user_agent(user_agent: &str) -> Self {
+        if ["iPhone", "iPad", "iPod"]
+            .iter()
+            .any(|needle| user_agent.contains(needle))
+        {
+            Self::Ios
+        } else if user_agent.contains("Android") {
+            Self::Android
+        } else if ["Windows NT", "Macintosh", "X11", "CrOS"]
+            .iter()
+            .any(|needle| user_agent.contains(needle))
+        {
+            Self::Desktop
+        } else {
+            Self::Other
+        }
+    }
+}
+
+const BROWSERS: &[(&str, &str)] = &[
+    ("Edg", "Edge"),
+    ("OPR/", "Opera"),
+    ("Opera", "Opera"),
+    ("SamsungBrowser/", "Samsung Internet"),
+    ("FxiOS/", "Firefox"),
+    ("Firefox/", "Firefox"),
+    ("CriOS/", "Chrome"),
+    ("Chromium/", "Chromium"),
+    ("Chrome/", "Chrome"),
+    ("Version/", "Safari"),
+    ("curl/", "curl"),
+    ("Wget/", "Wget"),
+];
+
+const OPERATING_SYSTEMS: &[(&str, &str)] = &[
+    ("Windows", "Windows"),
+    ("iPhone", "iOS"),
+    ("iPad", "iOS"),
+    ("iPod", "iOS"),
+    ("Mac OS X", "macOS"),
+    ("Macintosh", "macOS"),
+    ("Android", "Android"),
+    ("CrOS", "ChromeOS"),
+    ("Linux", "Linux"),
+];
+
+fn is_bot(user_agent: &str) -> bool {
+    let lowercase = user_agent.to_lowercase();
+
+    ["bot", "crawler", "spider"]
+        .iter()
+        .any(|needle| lowercase.contains(needle))
+}
+
+pub fn browser(user_agent: &str) -> &'static str {
+    if is_social_crawler(user_agent) {
+        return "Social crawler";
+    }
+
+    if is_bot(user_agent) {
+        return "Bot";
+    }
+
+    BROWSERS
+        .iter()
+        .find(|(needle, _)| user_agent.contains(needle))
+        .map_or("Other", |(_, browser)| browser)
+}
+
+pub fn os(user_agent: &str) -> &'static str {
+    OPERATING_SYSTEMS
+        .iter()
+        .find(|(needle, _)| user_agent.contains(needle))
+        .map_or("Other", |(_, os)| os)
+}
+
+pub fn device(user_agent: &str) -> &'static str {
+    if is_social_crawler(user_agent) || is_bot(user_agent) {
+        return "Bot";
+    }
+
+    if user_agent.contains("iPad")
+        || user_agent.contains("Tablet")
+        || (user_agent.contains("Android") && !user_agent.contains("Mobile"))
+    {
+        return "Tablet";
+    }
+
+    match Platform::from_user_agent(user_agent) {
+        Platform::Ios | Platform::Android => "Mobile",
+        Platform::Desktop => "Desktop",
+        Platform::Other if user_agent.contains("Mobi") => "Mobile",
+        Platform::Other => "Other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_social_crawlers_but_not_in_app_browsers() {
+        assert!(is_social_crawler(
+            "Mozilla/5.0 (compatible; Pinterestbot/1.0; +http://www.pinterest.com/bot.html)"
+        ));
+        assert!(!is_social_crawler(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 [Pinterest/iOS]"
+        ));
+        assert!(!is_social_crawler(
+            "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36 [Pinterest/Android]"
+        ));
+    }
+}
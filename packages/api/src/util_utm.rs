@@ -0,0 +1,49 @@
+use {
+    crate::util_url::merge_query_pairs,
+    serde::{Deserialize, Serialize},
+    url::Url,
+    utoipa::ToSchema,
+};
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, ToSchema)]
+pub struct UtmParameters {
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub utm_term: Option<String>,
+    pub utm_content: Option<String>,
+}
+
+impl UtmParameters {
+    fn pairs(&self) -> Vec<(String, String)> {
+        [
+            ("utm_source", &self.utm_source),
+            ("utm_medium", &self.utm_medium),
+            ("utm_campaign", &self.utm_campaign),
+            ("utm_term", &self.utm_term),
+            ("utm_content", &self.utm_content),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value
+                .as_ref()
+                .filter(|value| !value.is_empty())
+                .map(|value| (name.to_string(), value.clone()))
+        })
+        .collect()
+    }
+
+    pub fn apply(&self, url: &mut Url) {
+        merge_query_pairs(url, self.pairs());
+    }
+
+    pub fn apply_defaults(&self, url: &mut Url) {
+        let pairs = self
+            .pairs()
+            .into_iter()
+            .filter(|(name, _)| !url.query_pairs().any(|(existing, _)| existing == *name))
+            .collect();
+
+        merge_query_pairs(url, pairs);
+    }
+}